@@ -1,228 +1,561 @@
-use crate::cdp::{CdpBrowser, CdpPage};
-use crate::config::Config;
-use crate::models::{Paper, Paragraph};
-use anyhow::Result;
+use crate::backend::{ArxivApi, ArxivBackend, SearchPage};
+use crate::category::Category;
+use crate::config::{Backend, Config};
+use crate::date;
+use crate::id::ArxivId;
+#[cfg(feature = "pdf")]
+use crate::models::Paragraph;
+use crate::models::{Paper, Version};
+use crate::query::SearchQuery;
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use futures::future::{self, BoxFuture};
+use futures::stream::{self, FuturesOrdered};
+use futures::{FutureExt, Stream, StreamExt, TryStreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Key used to order search results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortBy {
+    /// arXiv's relevance ranking
+    #[default]
+    Relevance,
+    /// Submission date
+    Submitted,
+    /// Announcement date
+    Announced,
+    /// Date of the last update
+    Updated,
+}
 
-pub struct ArxivClient {
-    browser: CdpBrowser,
+/// Direction of the sort order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortOrder {
+    #[value(name = "asc", alias = "ascending")]
+    Ascending,
+    #[default]
+    #[value(name = "desc", alias = "descending")]
+    Descending,
 }
 
-impl ArxivClient {
-    pub async fn new(config: &Config) -> Result<Self> {
-        let args = vec![
-            "--user-agent=Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
-        ];
-        let browser_path = config.browser_path.as_ref().map(std::path::PathBuf::from);
-        let browser = CdpBrowser::launch(browser_path, args, config.headless, false).await?;
-        Ok(Self { browser })
-    }
-
-    pub async fn search(
-        &self,
-        query: &str,
-        limit: Option<usize>,
-        after: Option<String>,
-        before: Option<String>,
-    ) -> Result<Vec<Paper>> {
-        let mut all_papers = Vec::new();
-        let limit_val = limit.unwrap_or(usize::MAX);
-        let chunk_size = 50;
-        let mut start = 0;
+/// Date the `after`/`before` filters are applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DateType {
+    /// Original submission date
+    #[default]
+    Submitted,
+    /// Submission date of the latest version
+    Updated,
+    /// Announcement date
+    Announced,
+}
+
+/// Parameters of a search request
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Terms to search for
+    pub query: SearchQuery,
+    /// Maximum number of results to return (unlimited when `None`)
+    pub limit: Option<usize>,
+    /// Only return papers dated on or after this date (`YYYY-MM-DD`)
+    pub after: Option<String>,
+    /// Only return papers dated on or before this date (`YYYY-MM-DD`)
+    pub before: Option<String>,
+    /// Which date `after` and `before` refer to
+    pub date_type: DateType,
+    /// Restrict results to any of these subject groups, archives or categories
+    pub categories: Vec<Category>,
+    /// Ignore cross-listed papers when filtering by group or archive
+    pub exclude_cross_list: bool,
+    /// Key the results are ordered by, relevance unless set
+    pub sort_by: SortBy,
+    /// Whether `sort_by` runs ascending or descending
+    pub sort_order: SortOrder,
+}
+
+impl SearchOptions {
+    pub fn new(query: impl Into<SearchQuery>) -> Self {
+        Self { query: query.into(), ..Default::default() }
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn after(mut self, date: impl Into<String>) -> Self {
+        self.after = Some(date.into());
+        self
+    }
+
+    pub fn before(mut self, date: impl Into<String>) -> Self {
+        self.before = Some(date.into());
+        self
+    }
+
+    pub fn date_type(mut self, date_type: DateType) -> Self {
+        self.date_type = date_type;
+        self
+    }
+
+    pub fn sort(mut self, sort_by: SortBy, sort_order: SortOrder) -> Self {
+        self.sort_by = sort_by;
+        self.sort_order = sort_order;
+        self
+    }
+
+    pub fn category(mut self, category: Category) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    pub fn exclude_cross_list(mut self, exclude: bool) -> Self {
+        self.exclude_cross_list = exclude;
+        self
+    }
+
+    /// Check the query and that the date filters are `YYYY-MM-DD` dates in order
+    pub fn validate(&self) -> Result<()> {
+        self.query.validate()?;
+        let after = self.after.as_deref().map(date::parse_ymd).transpose()?;
+        let before = self.before.as_deref().map(date::parse_ymd).transpose()?;
+        if let (Some(after), Some(before)) = (after, before)
+            && after > before
+        {
+            return Err(anyhow!("Invalid date range: {} is later than {}", after, before));
+        }
+        Ok(())
+    }
+}
+
+/// Earliest submission date on arXiv, used as the lower bound when splitting open date ranges
+const FIRST_SUBMISSION: NaiveDate = NaiveDate::from_ymd_opt(1991, 8, 1).unwrap();
+
+/// Page-by-page state of a running search
+///
+/// When the backend caps how many hits can be paged through, the date range is split into
+/// smaller windows which are harvested one after another in the requested date order; papers
+/// are deduplicated by ID.
+struct Harvest<'a> {
+    backend: &'a dyn ArxivBackend,
+    options: &'a SearchOptions,
+    limit: usize,
+    seen: HashSet<ArxivId>,
+    started: bool,
+    /// Date windows still to harvest, ordered so that `pop` yields the next one
+    windows: Vec<(NaiveDate, NaiveDate)>,
+    /// Whether windows are harvested newest first, following a descending date sort
+    newest_first: bool,
+    /// Date the windows filter on, the one the results are sorted by
+    split_on: DateType,
+    /// First page of the whole range, fetched by `start`, reused by the first window
+    prefetched: Option<SearchPage>,
+    cursor: Option<Cursor<'a>>,
+}
+
+/// Pagination position within a single query
+struct Cursor<'a> {
+    options: Arc<SearchOptions>,
+    /// Offset of the next page to request
+    start: usize,
+    /// Offset past the last reachable result, once the backend reported the total
+    end: Option<usize>,
+    /// Pages already requested, yielded in offset order
+    pending: FuturesOrdered<BoxFuture<'a, Result<SearchPage>>>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(backend: &dyn ArxivBackend, options: SearchOptions, first_page: SearchPage) -> Self {
+        let cap = backend.max_results().unwrap_or(usize::MAX);
+        let mut pending = FuturesOrdered::new();
+        let end = first_page.total.map(|total| total.min(cap));
+        pending.push_back(future::ready(Ok(first_page)).boxed());
+        Self { options: Arc::new(options), start: backend.page_size(), end, pending }
+    }
+
+    /// Request pages until `wanted` are in flight, stopping at the last reachable result
+    fn request(&mut self, backend: &'a dyn ArxivBackend, wanted: usize) {
+        while self.pending.len() < wanted && self.end.is_none_or(|end| self.start < end) {
+            let (options, start) = (self.options.clone(), self.start);
+            self.pending
+                .push_back(async move { search_page(backend, &options, start).await }.boxed());
+            self.start += backend.page_size();
+        }
+    }
+}
+
+impl<'a> Harvest<'a> {
+    fn new(backend: &'a dyn ArxivBackend, options: &'a SearchOptions) -> Self {
+        Self {
+            backend,
+            options,
+            limit: options.limit.unwrap_or(usize::MAX),
+            seen: HashSet::new(),
+            started: false,
+            windows: Vec::new(),
+            newest_first: options.sort_order == SortOrder::Descending,
+            split_on: options.date_type,
+            prefetched: None,
+            cursor: None,
+        }
+    }
+
+    /// Fetch the next page of new papers, `None` once the search is exhausted
+    async fn next_batch(&mut self) -> Result<Option<Vec<Paper>>> {
+        if !self.started {
+            self.started = true;
+            self.start().await?;
+        }
 
         loop {
-            if all_papers.len() >= limit_val {
-                break;
+            if self.seen.len() >= self.limit {
+                return Ok(None);
             }
 
-            let ws_url = self.browser.new_page().await?;
-            let tab = CdpPage::new(&ws_url).await?;
-
-            let url = Self::build_search_url(query, start, &after, &before);
-
-            tab.goto(&url).await?;
-            // tab.wait_until_navigated()?; // CDP helper doesn't have this, wait for element instead
-
-            // Wait for results to load or check if no results
-            // Use a custom wait loop to detect "no results" message quickly
-            let wait_script = include_str!("scripts/check_search_results.js");
-
-            let mut status = "timeout";
-            let start_time = std::time::Instant::now();
-            while start_time.elapsed().as_secs() < 60 {
-                let val = tab.evaluate(wait_script).await?;
-                if let Some(s) = val.as_str() {
-                    match s {
-                        "found" => {
-                            status = "found";
-                            break;
-                        }
-                        "empty" => {
-                            status = "empty";
-                            break;
-                        }
-                        "abstract" => {
-                            status = "abstract";
-                            break;
-                        }
-                        _ => {}
+            let Some(cursor) = self.cursor.as_mut() else {
+                match self.windows.pop() {
+                    Some((from, to)) => {
+                        self.open_window(from, to).await?;
+                        continue;
                     }
+                    None => return Ok(None),
                 }
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            };
+
+            // Once the total is known, later pages are requested ahead, up to the backend's
+            // concurrency and no further than the limit needs
+            let page_size = self.backend.page_size();
+            let needed = (self.limit - self.seen.len()).div_ceil(page_size).max(1);
+            let ahead = if cursor.end.is_some() { self.backend.concurrency().max(1) } else { 1 };
+            cursor.request(self.backend, ahead.min(needed));
+
+            let Some(page) = cursor.pending.next().await.transpose()? else {
+                self.cursor = None;
+                continue;
+            };
+            let exhausted =
+                cursor.pending.is_empty() && cursor.end.is_some_and(|end| cursor.start >= end);
+            if page.papers.is_empty() || exhausted {
+                self.cursor = None;
             }
 
-            if status == "abstract" {
-                let js_script = include_str!("scripts/extract_paper.js");
-                let value = tab.evaluate(js_script).await?;
-                let json_str: String = serde_json::from_value(value)?;
-                let paper: Paper = serde_json::from_str(&json_str)?;
-                all_papers.push(paper);
-                break; // Single result from redirect
+            let mut batch = Vec::new();
+            for paper in page.papers {
+                if self.seen.len() >= self.limit {
+                    break;
+                }
+                if self.seen.insert(paper.id.clone()) {
+                    batch.push(paper);
+                }
             }
+            return Ok(Some(batch));
+        }
+    }
 
-            if status != "found" {
-                break; // No more results found, empty, or timeout
+    /// Fetch the first page and decide whether the date range has to be split
+    async fn start(&mut self) -> Result<()> {
+        self.options.validate()?;
+
+        let first_page = search_page(self.backend, self.options, 0).await?;
+        match (first_page.total, self.backend.max_results()) {
+            (Some(total), Some(cap)) if total > cap && self.limit > cap => {
+                // Windows on the sort date continue each other's order, but relevance rankings
+                // cannot be merged
+                self.split_on = match self.options.sort_by {
+                    SortBy::Relevance => {
+                        return Err(anyhow!(
+                            "{} results exceed the {} that can be paged through for one query; \
+                             sort by date (e.g. --sort submitted) to harvest them in date windows",
+                            total,
+                            cap
+                        ));
+                    }
+                    SortBy::Submitted => DateType::Submitted,
+                    SortBy::Updated => DateType::Updated,
+                    SortBy::Announced => DateType::Announced,
+                };
+                let filtered = self.options.after.is_some() || self.options.before.is_some();
+                if filtered && self.split_on != self.options.date_type {
+                    return Err(anyhow!(
+                        "{} results exceed the {} that can be paged through for one query; to \
+                         harvest them in date windows the date filters and the sort must use the \
+                         same date",
+                        total,
+                        cap
+                    ));
+                }
+                let from = match &self.options.after {
+                    Some(after) => date::parse_ymd(after)?,
+                    None => FIRST_SUBMISSION,
+                };
+                let to = match &self.options.before {
+                    Some(before) => date::parse_ymd(before)?,
+                    None => chrono::Utc::now().date_naive(),
+                };
+                self.windows.push((from, to));
+                self.prefetched = Some(first_page);
             }
+            _ => {
+                self.cursor = Some(Cursor::new(self.backend, self.options.clone(), first_page));
+            }
+        }
+        Ok(())
+    }
 
-            let js_script = include_str!("scripts/extract_search_results.js");
+    /// Split the window if it still exceeds the cap, otherwise start paging through it
+    async fn open_window(&mut self, from: NaiveDate, to: NaiveDate) -> Result<()> {
+        let mut window = self.options.clone();
+        window.after = Some(from.format("%Y-%m-%d").to_string());
+        window.before = Some(to.format("%Y-%m-%d").to_string());
+        window.date_type = self.split_on;
+
+        // The first window is the whole range, whose first page `start` already has
+        let first_page = match self.prefetched.take() {
+            Some(page) => page,
+            None => search_page(self.backend, &window, 0).await?,
+        };
+        let total = first_page.total.unwrap_or_default();
+        let cap = self.backend.max_results().unwrap_or(usize::MAX);
+
+        if total > cap && from < to {
+            let mid = from + (to - from) / 2;
+            let (older, newer) = ((from, mid), (mid.succ_opt().unwrap_or(mid), to));
+            let (next, later) = if self.newest_first { (newer, older) } else { (older, newer) };
+            self.windows.push(later);
+            self.windows.push(next);
+            return Ok(());
+        }
 
-            let value = tab.evaluate(js_script).await?;
+        eprintln!("Harvesting {} to {} ({} results)", from, to, total);
+        if total > cap {
+            eprintln!(
+                "Warning: only the first {} results of {} from {} to {} can be retrieved",
+                cap, total, from, to
+            );
+        }
+        self.cursor = Some(Cursor::new(self.backend, window, first_page));
+        Ok(())
+    }
+}
 
-            let json_str: String = serde_json::from_value(value)?;
-            let papers: Vec<Paper> = serde_json::from_str(&json_str)?;
+/// Request a search page, restarting the backend and retrying once if its transport failed
+async fn search_page(
+    backend: &dyn ArxivBackend,
+    options: &SearchOptions,
+    start: usize,
+) -> Result<SearchPage> {
+    let result = backend.search_page(options, start).await;
+    if let Err(e) = &result
+        && recover(backend, e).await?
+    {
+        return backend.search_page(options, start).await;
+    }
+    result
+}
 
-            if papers.is_empty() {
-                break;
-            }
+/// Fetch a paper's metadata, restarting the backend and retrying once if its transport failed
+async fn fetch_paper(backend: &dyn ArxivBackend, id: &ArxivId) -> Result<Paper> {
+    let result = backend.fetch(id).await;
+    if let Err(e) = &result
+        && recover(backend, e).await?
+    {
+        return backend.fetch(id).await;
+    }
+    result
+}
 
-            all_papers.extend(papers);
+/// Restart the backend if `error` was caused by a failed transport, returning whether it was
+async fn recover(backend: &dyn ArxivBackend, error: &anyhow::Error) -> Result<bool> {
+    let Some(failure) = backend.failure() else {
+        return Ok(false);
+    };
+    eprintln!("{} ({}), restarting and retrying", error, failure);
+    backend.restart().await.with_context(|| format!("Failed to restart after: {}", failure))?;
+    Ok(true)
+}
 
-            // Close tab to save resources, though headless_chrome might handle this on drop, explicit is safer for loop
+/// Client for searching and fetching arXiv papers
+///
+/// Use [`ArxivClient::builder`] to configure it, or [`ArxivClient::new`] to build one from a
+/// loaded [`Config`].
+pub struct ArxivClient {
+    backend: Box<dyn ArxivBackend>,
+}
 
-            // Tab closes when dropped? No, CdpPage doesn't own the tab in browser, it just connects.
-            // But for this simple implementation we just open new tabs.
-            // CdpBrowser drop will kill key process.
-            // google-patent-cli doesn't explicit close tabs in loop?
-            // It seems CdpPage doesn't have close method.
-            // This might leak tabs in long loop.
-            // But we can just proceed for now matching the structure.
+/// Builder for [`ArxivClient`]
+#[derive(Default)]
+pub struct ArxivClientBuilder {
+    config: Config,
+    backend: Option<Box<dyn ArxivBackend>>,
+}
 
-            start += chunk_size;
-        }
+impl ArxivClientBuilder {
+    /// Start from an existing configuration
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
 
-        #[allow(clippy::collapsible_if)]
-        if let Some(n) = limit {
-            if all_papers.len() > n {
-                all_papers.truncate(n);
-            }
-        }
+    /// Select one of the built-in backends
+    pub fn backend_kind(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Use a custom backend implementation instead of a built-in one
+    pub fn backend(mut self, backend: Box<dyn ArxivBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Endpoint of the arXiv Atom API used by the `api` backend
+    pub fn api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.config.api_base_url = url.into();
+        self
+    }
 
-        Ok(all_papers)
+    /// Run Chrome headless (default) or with a visible window
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.config.headless = headless;
+        self
     }
 
-    pub async fn fetch(&self, id: &str) -> Result<Paper> {
-        let ws_url = self.browser.new_page().await?;
-        let tab = CdpPage::new(&ws_url).await?;
-        let url = Self::build_fetch_url(id);
+    /// Path of the Chrome executable used by the `browser` backend
+    pub fn browser_path(mut self, path: impl Into<String>) -> Self {
+        self.config.browser_path = Some(path.into());
+        self
+    }
+
+    /// Number of search pages the `browser` backend loads in parallel
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.config.concurrency = concurrency;
+        self
+    }
+
+    /// Minimum delay between two page loads of the `browser` backend
+    pub fn request_delay(mut self, delay: std::time::Duration) -> Self {
+        self.config.request_delay_ms = delay.as_millis() as u64;
+        self
+    }
 
-        tab.goto(&url).await?;
+    /// Build the client, launching the browser if the `browser` backend is selected
+    pub async fn build(self) -> Result<ArxivClient> {
+        let backend = match self.backend {
+            Some(backend) => backend,
+            None => Self::builtin_backend(&self.config).await?,
+        };
+        Ok(ArxivClient { backend })
+    }
 
-        if !tab.wait_for_element("h1.title", 10).await? {
-            return Err(anyhow::anyhow!("Paper page not loaded correctly or timeout"));
+    async fn builtin_backend(config: &Config) -> Result<Box<dyn ArxivBackend>> {
+        match config.backend {
+            #[cfg(feature = "browser")]
+            Backend::Browser => Ok(Box::new(crate::backend::BrowserBackend::launch(config).await?)),
+            #[cfg(not(feature = "browser"))]
+            Backend::Browser => {
+                anyhow::bail!("The browser backend requires the `browser` feature")
+            }
+            Backend::Api => Ok(Box::new(ArxivApi::new(&config.api_base_url))),
         }
+    }
+}
+
+impl ArxivClient {
+    /// Create a client from a loaded configuration
+    pub async fn new(config: &Config) -> Result<Self> {
+        Self::builder().config(config.clone()).build().await
+    }
+
+    pub fn builder() -> ArxivClientBuilder {
+        ArxivClientBuilder::default()
+    }
+
+    /// Create a client on top of an arbitrary backend implementation
+    pub fn with_backend(backend: Box<dyn ArxivBackend>) -> Self {
+        Self { backend }
+    }
 
-        let js_script = include_str!("scripts/extract_paper.js");
+    /// Search for papers, following pagination until `options.limit` is reached
+    ///
+    /// Queries above the backend's result cap are split into date windows.
+    pub async fn search(&self, options: &SearchOptions) -> Result<Vec<Paper>> {
+        self.search_stream(options).try_collect().await
+    }
 
-        let value = tab.evaluate(js_script).await?;
+    /// Search for papers, yielding each paper as soon as its results page is extracted
+    pub fn search_stream<'a>(
+        &'a self,
+        options: &'a SearchOptions,
+    ) -> impl Stream<Item = Result<Paper>> + Send + 'a {
+        let harvest = Harvest::new(self.backend.as_ref(), options);
+        stream::try_unfold(harvest, |mut harvest| async move {
+            anyhow::Ok(harvest.next_batch().await?.map(|batch| (batch, harvest)))
+        })
+        .map_ok(|batch| stream::iter(batch.into_iter().map(Ok)))
+        .try_flatten()
+    }
 
-        let json_str: String = serde_json::from_value(value)?;
-        let mut paper: Paper = serde_json::from_str(&json_str)?;
+    /// Fetch a paper's metadata and, with the `pdf` feature, its full text as paragraphs
+    pub async fn fetch(&self, id: &ArxivId) -> Result<Paper> {
+        #[cfg_attr(not(feature = "pdf"), allow(unused_mut))]
+        let mut paper = fetch_paper(self.backend.as_ref(), id).await?;
 
         // Fetch PDF and extract text
+        #[cfg(feature = "pdf")]
         if !paper.pdf_url.is_empty() {
-            let pdf_url = paper.pdf_url.clone();
-            let pdf_text = tokio::task::spawn_blocking(move || {
-                match reqwest::blocking::get(&pdf_url) {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            let bytes = response.bytes().ok()?;
-                            // Use tempfile to write bytes for pdf-extract
-                            let mut temp_file = tempfile::NamedTempFile::new().ok()?;
-                            use std::io::Write;
-                            temp_file.write_all(&bytes).ok()?;
-
-                            match pdf_extract::extract_text(temp_file.path()) {
-                                Ok(text) => {
-                                    let paragraphs: Vec<Paragraph> = text
-                                        .split("\n\n")
-                                        .map(|s| s.trim().to_string())
-                                        .filter(|s| !s.is_empty())
-                                        .enumerate()
-                                        .map(|(i, s)| Paragraph {
-                                            number: format!("{:04}", i + 1),
-                                            id: String::new(),
-                                            text: s,
-                                        })
-                                        .collect();
-                                    Some(paragraphs)
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to extract text from PDF: {}", e);
-                                    None
-                                }
-                            }
-                        } else {
-                            eprintln!("Failed to download PDF: Status {}", response.status());
-                            None
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to download PDF: {}", e);
-                        None
-                    }
+            paper.description_paragraphs = match self.backend.fetch_pdf(&paper).await {
+                Ok(bytes) => {
+                    tokio::task::spawn_blocking(move || Self::extract_paragraphs(&bytes)).await?
                 }
-            })
-            .await?;
-
-            paper.description_paragraphs = pdf_text;
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            };
         }
 
         Ok(paper)
     }
 
-    pub async fn fetch_pdf(&self, id: &str) -> Result<Vec<u8>> {
-        let paper = self.fetch(id).await?;
-        let response = reqwest::get(&paper.pdf_url).await?;
-        let bytes = response.bytes().await?;
-        Ok(bytes.to_vec())
-    }
-
-    fn build_search_url(
-        query: &str,
-        start: usize,
-        after: &Option<String>,
-        before: &Option<String>,
-    ) -> String {
-        let encoded_query = urlencoding::encode(query);
-        if after.is_some() || before.is_some() {
-            let from_date = after.as_deref().unwrap_or("");
-            let to_date = before.as_deref().unwrap_or("");
-            format!(
-                "https://arxiv.org/search/advanced?advanced=1&terms-0-operator=AND&terms-0-term={}&terms-0-field=all&classification-physics_archives=all&classification-include_cross_list=include&date-filter_by=date_range&date-from_date={}&date-to_date={}&date-date_type=submitted_date&abstracts=show&size=50&order=-announced_date_first&start={}",
-                encoded_query, from_date, to_date, start
-            )
-        } else {
-            format!(
-                "https://arxiv.org/search/?query={}&searchtype=all&source=header&start={}",
-                encoded_query, start
-            )
-        }
+    /// List every version of a paper with its submission time and size
+    pub async fn versions(&self, id: &ArxivId) -> Result<Vec<Version>> {
+        self.backend.versions(id).await
     }
 
-    fn build_fetch_url(id: &str) -> String {
-        if id.starts_with("http") {
-            id.to_string()
-        } else {
-            format!("https://arxiv.org/abs/{}", id)
+    /// Download the PDF of a paper
+    pub async fn fetch_pdf(&self, id: &ArxivId) -> Result<Vec<u8>> {
+        let paper = fetch_paper(self.backend.as_ref(), id).await?;
+        self.backend.fetch_pdf(&paper).await
+    }
+
+    #[cfg(feature = "pdf")]
+    fn extract_paragraphs(bytes: &[u8]) -> Option<Vec<Paragraph>> {
+        // Use tempfile to write bytes for pdf-extract
+        let mut temp_file = tempfile::NamedTempFile::new().ok()?;
+        use std::io::Write;
+        temp_file.write_all(bytes).ok()?;
+
+        match pdf_extract::extract_text(temp_file.path()) {
+            Ok(text) => {
+                let paragraphs: Vec<Paragraph> = text
+                    .split("\n\n")
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .enumerate()
+                    .map(|(i, s)| Paragraph {
+                        number: format!("{:04}", i + 1),
+                        id: String::new(),
+                        text: s,
+                    })
+                    .collect();
+                Some(paragraphs)
+            }
+            Err(e) => {
+                eprintln!("Failed to extract text from PDF: {}", e);
+                None
+            }
         }
     }
 }
@@ -230,51 +563,359 @@ impl ArxivClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use chrono::Datelike;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// In-memory backend serving `total` numbered papers
+    struct FakeBackend {
+        total: usize,
+        requested_starts: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl FakeBackend {
+        fn new(total: usize) -> Self {
+            Self { total, requested_starts: Arc::new(Mutex::new(Vec::new())) }
+        }
 
-    #[test]
-    fn test_build_search_url_simple() {
-        let url = ArxivClient::build_search_url("LLM", 0, &None, &None);
-        assert_eq!(url, "https://arxiv.org/search/?query=LLM&searchtype=all&source=header&start=0");
+        fn paper(n: usize) -> Paper {
+            Paper {
+                id: format!("2401.{:05}", n).parse().unwrap(),
+                title: format!("Paper {}", n),
+                authors: vec!["Author".to_string()],
+                ..Default::default()
+            }
+        }
     }
 
-    #[test]
-    fn test_build_search_url_with_pagination() {
-        let url = ArxivClient::build_search_url("LLM", 50, &None, &None);
-        assert_eq!(
-            url,
-            "https://arxiv.org/search/?query=LLM&searchtype=all&source=header&start=50"
+    #[async_trait]
+    impl ArxivBackend for FakeBackend {
+        fn page_size(&self) -> usize {
+            10
+        }
+
+        async fn search_page(&self, _options: &SearchOptions, start: usize) -> Result<SearchPage> {
+            self.requested_starts.lock().unwrap().push(start);
+            let papers = (start..self.total.min(start + 10)).map(Self::paper).collect();
+            Ok(SearchPage { papers, total: None })
+        }
+
+        async fn fetch(&self, id: &ArxivId) -> Result<Paper> {
+            let versions = (1..=2)
+                .map(|version| Version {
+                    version,
+                    submitted: format!("Mon, {} Jan 2024 00:00:00 UTC", version),
+                    size_kb: Some(100),
+                })
+                .collect();
+            Ok(Paper {
+                id: id.without_version(),
+                version: id.version().or(Some(2)),
+                versions,
+                ..Self::paper(0)
+            })
+        }
+    }
+
+    fn client(total: usize) -> ArxivClient {
+        ArxivClient::with_backend(Box::new(FakeBackend::new(total)))
+    }
+
+    #[tokio::test]
+    async fn test_search_paginates_until_empty_page() {
+        let papers = client(25).search(&SearchOptions::new("LLM")).await.unwrap();
+        assert_eq!(papers.len(), 25);
+        assert_eq!(papers[24].id, "2401.00024");
+    }
+
+    #[tokio::test]
+    async fn test_search_respects_limit() {
+        let backend = FakeBackend::new(100);
+        let requested_starts = backend.requested_starts.clone();
+        let client = ArxivClient::with_backend(Box::new(backend));
+        let papers = client.search(&SearchOptions::new("LLM").limit(15)).await.unwrap();
+        assert_eq!(papers.len(), 15);
+        assert_eq!(*requested_starts.lock().unwrap(), vec![0, 10]);
+    }
+
+    /// Backend reporting 95 results, answering later pages first and recording the requests
+    struct ConcurrentBackend {
+        in_flight: AtomicUsize,
+        peak: Arc<AtomicUsize>,
+        requested_starts: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait]
+    impl ArxivBackend for ConcurrentBackend {
+        fn page_size(&self) -> usize {
+            10
+        }
+
+        fn concurrency(&self) -> usize {
+            4
+        }
+
+        async fn search_page(&self, _options: &SearchOptions, start: usize) -> Result<SearchPage> {
+            self.requested_starts.lock().unwrap().push(start);
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(100 - start as u64)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let papers = (start..95.min(start + 10)).map(FakeBackend::paper).collect();
+            Ok(SearchPage { papers, total: Some(95) })
+        }
+
+        async fn fetch(&self, _id: &ArxivId) -> Result<Paper> {
+            Err(anyhow!("not supported"))
+        }
+    }
+
+    fn concurrent_client() -> (ArxivClient, Arc<AtomicUsize>, Arc<Mutex<Vec<usize>>>) {
+        let backend = ConcurrentBackend {
+            in_flight: AtomicUsize::new(0),
+            peak: Arc::new(AtomicUsize::new(0)),
+            requested_starts: Arc::new(Mutex::new(Vec::new())),
+        };
+        let (peak, starts) = (backend.peak.clone(), backend.requested_starts.clone());
+        (ArxivClient::with_backend(Box::new(backend)), peak, starts)
+    }
+
+    #[tokio::test]
+    async fn test_search_fetches_pages_concurrently_in_order() {
+        let (client, peak, _) = concurrent_client();
+        let papers = client.search(&SearchOptions::new("LLM")).await.unwrap();
+        let ids = papers.iter().map(|p| p.id.to_string()).collect::<Vec<_>>();
+        let expected = (0..95).map(|n| format!("2401.{:05}", n)).collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+        assert_eq!(peak.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_search_does_not_fetch_past_limit() {
+        let (client, _, starts) = concurrent_client();
+        let papers = client.search(&SearchOptions::new("LLM").limit(25)).await.unwrap();
+        assert_eq!(papers.len(), 25);
+        assert_eq!(*starts.lock().unwrap(), vec![0, 10, 20]);
+    }
+
+    /// Backend whose transport crashes on the page at offset 10 until it is restarted
+    #[derive(Default)]
+    struct CrashingBackend {
+        crashed: AtomicBool,
+        restarts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ArxivBackend for CrashingBackend {
+        fn page_size(&self) -> usize {
+            10
+        }
+
+        fn failure(&self) -> Option<String> {
+            self.crashed.load(Ordering::SeqCst).then(|| "browser exited".to_string())
+        }
+
+        async fn restart(&self) -> Result<()> {
+            self.restarts.fetch_add(1, Ordering::SeqCst);
+            self.crashed.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn search_page(&self, _options: &SearchOptions, start: usize) -> Result<SearchPage> {
+            if start == 10 && self.restarts.load(Ordering::SeqCst) == 0 {
+                self.crashed.store(true, Ordering::SeqCst);
+                return Err(anyhow!("CDP connection closed"));
+            }
+            let papers = (start..25.min(start + 10)).map(FakeBackend::paper).collect();
+            Ok(SearchPage { papers, total: None })
+        }
+
+        async fn fetch(&self, _id: &ArxivId) -> Result<Paper> {
+            Err(anyhow!("Paper page not loaded correctly or timeout"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restarts_failed_backend_and_retries_page() {
+        let backend = CrashingBackend::default();
+        let restarts = backend.restarts.clone();
+        let client = ArxivClient::with_backend(Box::new(backend));
+        let papers = client.search(&SearchOptions::new("LLM")).await.unwrap();
+        assert_eq!(papers.len(), 25);
+        assert_eq!(restarts.load(Ordering::SeqCst), 1);
+
+        // Errors with a healthy transport are reported without restarting
+        let id: ArxivId = "2401.00001".parse().unwrap();
+        assert!(client.fetch(&id).await.is_err());
+        assert_eq!(restarts.load(Ordering::SeqCst), 1);
+    }
+
+    /// `(after, before, start)` of a page request
+    type Request = (Option<String>, Option<String>, usize);
+
+    /// Backend with one paper per day in 2020 and a cap of 50 results per query, recording
+    /// each request
+    #[derive(Default)]
+    struct DatedBackend {
+        requests: Arc<Mutex<Vec<Request>>>,
+        date_types: Arc<Mutex<Vec<DateType>>>,
+    }
+
+    #[async_trait]
+    impl ArxivBackend for DatedBackend {
+        fn page_size(&self) -> usize {
+            10
+        }
+
+        fn max_results(&self) -> Option<usize> {
+            Some(50)
+        }
+
+        async fn search_page(&self, options: &SearchOptions, start: usize) -> Result<SearchPage> {
+            let request = (options.after.clone(), options.before.clone(), start);
+            self.requests.lock().unwrap().push(request);
+            self.date_types.lock().unwrap().push(options.date_type);
+            let parse = |d: &Option<String>| d.as_deref().map(date::parse_ymd);
+            let from = parse(&options.after).transpose()?.unwrap_or(NaiveDate::MIN);
+            let to = parse(&options.before).transpose()?.unwrap_or(NaiveDate::MAX);
+            let mut matching = NaiveDate::from_ymd_opt(2020, 1, 1)
+                .unwrap()
+                .iter_days()
+                .take(366)
+                .filter(|d| *d >= from && *d <= to)
+                .map(|d| FakeBackend::paper(d.ordinal() as usize))
+                .collect::<Vec<_>>();
+            if options.sort_by != SortBy::Relevance && options.sort_order == SortOrder::Descending {
+                matching.reverse();
+            }
+            let total = matching.len();
+            let papers =
+                matching.into_iter().skip(start).take(10.min(50usize.saturating_sub(start)));
+            Ok(SearchPage { papers: papers.collect(), total: Some(total) })
+        }
+
+        async fn fetch(&self, _id: &ArxivId) -> Result<Paper> {
+            Err(anyhow!("not supported"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_splits_date_range_above_cap() {
+        let backend = DatedBackend::default();
+        let requests = backend.requests.clone();
+        let client = ArxivClient::with_backend(Box::new(backend));
+        let options = SearchOptions::new("LLM")
+            .after("2020-01-01")
+            .before("2020-12-31")
+            .sort(SortBy::Submitted, SortOrder::Ascending);
+        let papers = client.search(&options).await.unwrap();
+        assert_eq!(papers.len(), 366);
+        let ids = papers.iter().map(|p| p.id.to_string()).collect::<Vec<_>>();
+        assert!(ids.is_sorted(), "not in ascending order");
+        assert_eq!(papers.first().unwrap().id, "2401.00001");
+        assert_eq!(papers.last().unwrap().id, "2401.00366");
+
+        // The first page of the whole range is fetched once and reused for its window
+        let requests = requests.lock().unwrap();
+        let whole = (Some("2020-01-01".to_string()), Some("2020-12-31".to_string()), 0);
+        assert_eq!(requests.iter().filter(|r| **r == whole).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_split_windows_follow_descending_sort() {
+        let client = ArxivClient::with_backend(Box::new(DatedBackend::default()));
+        let options = SearchOptions::new("LLM")
+            .after("2020-01-01")
+            .before("2020-12-31")
+            .sort(SortBy::Submitted, SortOrder::Descending);
+        let papers = client.search(&options).await.unwrap();
+        assert_eq!(papers.len(), 366);
+        let ids = papers.iter().rev().map(|p| p.id.to_string()).collect::<Vec<_>>();
+        assert!(ids.is_sorted(), "not in descending order");
+        assert_eq!(papers.first().unwrap().id, "2401.00366");
+    }
+
+    #[tokio::test]
+    async fn test_split_rejects_relevance_sort() {
+        let client = ArxivClient::with_backend(Box::new(DatedBackend::default()));
+        let options = SearchOptions::new("LLM").after("2020-01-01").before("2020-12-31");
+        let error = client.search(&options).await.unwrap_err();
+        assert!(error.to_string().contains("sort by date"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_split_windows_use_the_sort_date() {
+        let backend = DatedBackend::default();
+        let requests = backend.date_types.clone();
+        let client = ArxivClient::with_backend(Box::new(backend));
+
+        // Without date filters the windows switch to the date the results are sorted by
+        let options = SearchOptions::new("LLM").sort(SortBy::Updated, SortOrder::Ascending);
+        assert_eq!(client.search(&options.limit(60)).await.unwrap().len(), 60);
+        let date_types = requests.lock().unwrap().clone();
+        assert!(date_types[1..].iter().all(|date_type| *date_type == DateType::Updated));
+
+        let options = SearchOptions::new("LLM")
+            .after("2020-01-01")
+            .date_type(DateType::Submitted)
+            .sort(SortBy::Updated, SortOrder::Ascending);
+        let error = client.search(&options).await.unwrap_err();
+        assert!(error.to_string().contains("same date"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_invalid_dates() {
+        let client = ArxivClient::with_backend(Box::new(DatedBackend::default()));
+        for options in [
+            SearchOptions::new("LLM").after("2020/01/01"),
+            SearchOptions::new("LLM").before("yesterday"),
+            SearchOptions::new("LLM").after("2020-12-31").before("2020-01-01"),
+        ] {
+            assert!(client.search(&options).await.is_err(), "{:?}", options);
+        }
+        assert!(
+            SearchOptions::new("LLM").after("2020-01-01").before("2020-01-01").validate().is_ok()
         );
     }
 
-    #[test]
-    fn test_build_search_url_with_dates() {
-        let after = Some("2023-01-01".to_string());
-        let before = Some("2023-12-31".to_string());
-        let url = ArxivClient::build_search_url("LLM", 0, &after, &before);
-        assert!(url.contains("date-filter_by=date_range"));
-        assert!(url.contains("date-from_date=2023-01-01"));
-        assert!(url.contains("date-to_date=2023-12-31"));
+    #[tokio::test]
+    async fn test_search_does_not_split_when_limit_fits_cap() {
+        let client = ArxivClient::with_backend(Box::new(DatedBackend::default()));
+        let options = SearchOptions::new("LLM").after("2020-01-01").limit(30);
+        let papers = client.search(&options).await.unwrap();
+        assert_eq!(papers.len(), 30);
     }
 
-    #[test]
-    fn test_build_fetch_url_id() {
-        let url = ArxivClient::build_fetch_url("2512.04518");
-        assert_eq!(url, "https://arxiv.org/abs/2512.04518");
+    #[tokio::test]
+    async fn test_search_stream_fetches_pages_lazily() {
+        let backend = FakeBackend::new(100);
+        let requested_starts = backend.requested_starts.clone();
+        let client = ArxivClient::with_backend(Box::new(backend));
+        let options = SearchOptions::new("LLM");
+
+        let papers: Vec<Paper> =
+            client.search_stream(&options).take(3).try_collect().await.unwrap();
+        assert_eq!(papers.len(), 3);
+        assert_eq!(*requested_starts.lock().unwrap(), vec![0]);
     }
 
-    #[test]
-    fn test_build_fetch_url_full_url() {
-        let url = ArxivClient::build_fetch_url("https://arxiv.org/abs/2512.04518");
-        assert_eq!(url, "https://arxiv.org/abs/2512.04518");
+    #[tokio::test]
+    async fn test_fetch_without_pdf() {
+        let paper = client(0).fetch(&"2401.00001".parse().unwrap()).await.unwrap();
+        assert_eq!(paper.id, "2401.00001");
+        assert!(paper.description_paragraphs.is_none());
     }
 
-    #[test]
-    fn test_build_search_url_with_before_only() {
-        let before = Some("2023-10-13".to_string());
-        let url = ArxivClient::build_search_url("conversational data analysis", 0, &None, &before);
-        assert!(url.contains("date-filter_by=date_range"));
-        assert!(url.contains("date-from_date=&"));
-        assert!(url.contains("date-to_date=2023-10-13"));
-        assert!(url.contains("terms-0-term=conversational%20data%20analysis"));
+    #[tokio::test]
+    async fn test_fetch_specific_version_and_list_versions() {
+        let client = client(0);
+        let paper = client.fetch(&"2401.00001v1".parse().unwrap()).await.unwrap();
+        assert_eq!(paper.id, "2401.00001");
+        assert_eq!(paper.version, Some(1));
+
+        let versions = client.versions(&"2401.00001v1".parse().unwrap()).await.unwrap();
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![1, 2]);
     }
 }
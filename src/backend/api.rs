@@ -0,0 +1,601 @@
+use super::{ArxivBackend, SearchPage};
+use crate::arxiv_search::{DateType, SearchOptions, SortBy, SortOrder};
+use crate::category;
+use crate::date;
+use crate::id::ArxivId;
+use crate::models::{Paper, Version};
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Default endpoint of the arXiv Atom export API
+pub const DEFAULT_API_BASE_URL: &str = "https://export.arxiv.org/api/query";
+
+/// Number of entries requested per API call
+const PAGE_SIZE: usize = 100;
+
+/// Minimum delay between consecutive API calls, as requested by the arXiv API terms of use
+const REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Client for the arXiv Atom export API (no browser required)
+pub struct ArxivApi {
+    client: reqwest::Client,
+    base_url: String,
+    last_request: Mutex<Option<Instant>>,
+}
+
+/// A parsed page of the Atom feed
+#[derive(Debug)]
+struct Feed {
+    total_results: Option<usize>,
+    papers: Vec<Paper>,
+}
+
+#[async_trait]
+impl ArxivBackend for ArxivApi {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    async fn search_page(&self, options: &SearchOptions, start: usize) -> Result<SearchPage> {
+        let url = self.build_query_url(options, start)?;
+        let feed = self.get_feed(&url).await?;
+        Ok(SearchPage { papers: feed.papers, total: feed.total_results })
+    }
+
+    async fn fetch(&self, id: &ArxivId) -> Result<Paper> {
+        let url = self.build_fetch_url(id);
+        let feed = self.get_feed(&url).await?;
+
+        feed.papers.into_iter().next().ok_or_else(|| anyhow!("Paper not found: {}", id))
+    }
+
+    /// The Atom feed has no submission history, so it is read from the abstract page
+    async fn versions(&self, id: &ArxivId) -> Result<Vec<Version>> {
+        let html = self.get(&self.build_abs_url(&id.without_version())?).await?;
+        let versions = Self::parse_submission_history(&html);
+        if versions.is_empty() {
+            return Err(anyhow!("No submission history found for {}", id));
+        }
+        Ok(versions)
+    }
+}
+
+impl ArxivApi {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            last_request: Mutex::new(None),
+        }
+    }
+
+    async fn get_feed(&self, url: &str) -> Result<Feed> {
+        let body = self.get(url).await?;
+        Self::parse_feed(&body)
+    }
+
+    /// GET a page, keeping consecutive requests `REQUEST_INTERVAL` apart
+    async fn get(&self, url: &str) -> Result<String> {
+        {
+            let mut last_request = self.last_request.lock().await;
+            if let Some(elapsed) = last_request.map(|t| t.elapsed())
+                && elapsed < REQUEST_INTERVAL
+            {
+                tokio::time::sleep(REQUEST_INTERVAL - elapsed).await;
+            }
+            *last_request = Some(Instant::now());
+        }
+
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("arXiv API request failed: Status {}", response.status()));
+        }
+        Ok(response.text().await?)
+    }
+
+    fn build_query_url(&self, options: &SearchOptions, start: usize) -> Result<String> {
+        if options.exclude_cross_list {
+            return Err(anyhow!("Excluding cross-lists is not supported by the api backend"));
+        }
+
+        let (after, before) = (&options.after, &options.before);
+        let mut search_query = options.query.to_api_query();
+        let mut filters = Vec::new();
+
+        if let Some(categories) = category::api_query(&options.categories) {
+            filters.push(categories);
+        }
+
+        if after.is_some() || before.is_some() {
+            let date_field = match options.date_type {
+                DateType::Submitted => "submittedDate",
+                DateType::Updated => "lastUpdatedDate",
+                DateType::Announced => {
+                    return Err(anyhow!(
+                        "Filtering by announcement date is not supported by the api backend"
+                    ));
+                }
+            };
+            let from_date = after.as_deref().map_or("*".to_string(), |d| Self::api_date(d, "0000"));
+            let to_date = before.as_deref().map_or("*".to_string(), |d| Self::api_date(d, "2359"));
+            filters.push(format!("{}:[{} TO {}]", date_field, from_date, to_date));
+        }
+
+        if !filters.is_empty() {
+            if options.query.terms().len() > 1 {
+                search_query = format!("({})", search_query);
+            }
+            for filter in filters {
+                search_query.push_str(&format!(" AND {}", filter));
+            }
+        }
+
+        let sort_by = match options.sort_by {
+            SortBy::Relevance => "relevance",
+            SortBy::Submitted => "submittedDate",
+            SortBy::Updated => "lastUpdatedDate",
+            SortBy::Announced => {
+                return Err(anyhow!(
+                    "Sorting by announcement date is not supported by the api backend"
+                ));
+            }
+        };
+        let sort_order = match options.sort_order {
+            SortOrder::Ascending => "ascending",
+            SortOrder::Descending => "descending",
+        };
+
+        Ok(format!(
+            "{}?search_query={}&start={}&max_results={}&sortBy={}&sortOrder={}",
+            self.base_url,
+            urlencoding::encode(&search_query),
+            start,
+            PAGE_SIZE,
+            sort_by,
+            sort_order
+        ))
+    }
+
+    fn build_fetch_url(&self, id: &ArxivId) -> String {
+        format!("{}?id_list={}", self.base_url, urlencoding::encode(&id.to_string()))
+    }
+
+    /// Abstract page on the host serving the API, so a custom `api_base_url` covers it too
+    fn build_abs_url(&self, id: &ArxivId) -> Result<String> {
+        let base = reqwest::Url::parse(&self.base_url)
+            .with_context(|| format!("Invalid API base URL {}", self.base_url))?;
+        Ok(base.join(&format!("/abs/{}", id))?.to_string())
+    }
+
+    /// Convert `YYYY-MM-DD` into the `YYYYMMDDHHMM` form used by date filters
+    fn api_date(date: &str, time: &str) -> String {
+        format!("{}{}", date.replace('-', ""), time)
+    }
+
+    fn parse_feed(xml: &str) -> Result<Feed> {
+        let mut reader = Reader::from_str(xml);
+
+        let mut total_results = None;
+        let mut papers = Vec::new();
+        let mut entry: Option<Paper> = None;
+        let mut path: Vec<String> = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            match reader.read_event().context("Failed to parse arXiv API response")? {
+                Event::Start(e) => {
+                    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                    if name == "entry" {
+                        entry = Some(Paper::default());
+                    }
+                    path.push(name);
+                    text.clear();
+                }
+                Event::Empty(e) => {
+                    if let Some(paper) = entry.as_mut()
+                        && e.name().as_ref() == b"link"
+                    {
+                        let href = e.try_get_attribute("href")?.map(|a| a.unescape_value());
+                        let title = e.try_get_attribute("title")?.map(|a| a.unescape_value());
+                        if let (Some(href), Some(title)) = (href, title)
+                            && title?.as_ref() == "pdf"
+                        {
+                            paper.pdf_url = href?.into_owned();
+                        }
+                    } else if let Some(paper) = entry.as_mut()
+                        && e.name().as_ref() == b"arxiv:primary_category"
+                        && let Some(term) = e.try_get_attribute("term")?
+                    {
+                        paper.primary_category = Some(term.unescape_value()?.into_owned());
+                    } else if let Some(paper) = entry.as_mut()
+                        && e.name().as_ref() == b"category"
+                        && let Some(term) = e.try_get_attribute("term")?
+                    {
+                        paper.categories.push(term.unescape_value()?.into_owned());
+                    }
+                }
+                Event::Text(t) => text.push_str(&t.decode()?),
+                Event::GeneralRef(r) => {
+                    if let Some(c) = r.resolve_char_ref()? {
+                        text.push(c);
+                    } else if let Some(s) =
+                        quick_xml::escape::resolve_predefined_entity(&r.decode()?)
+                    {
+                        text.push_str(s);
+                    }
+                }
+                Event::End(_) => {
+                    let name = path.pop().unwrap_or_default();
+                    let parent = path.last().map(String::as_str);
+                    let value = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                    match (parent, name.as_str()) {
+                        (Some("feed"), "opensearch:totalResults") => {
+                            total_results = value.parse().ok();
+                        }
+                        (Some("feed"), "entry") => {
+                            if let Some(mut paper) = entry.take() {
+                                if paper.url.contains("/api/errors") {
+                                    return Err(anyhow!("arXiv API error: {}", paper.summary));
+                                }
+                                // Keep the primary category first, as on the abstract page
+                                if let Some(primary) = &paper.primary_category
+                                    && let Some(pos) =
+                                        paper.categories.iter().position(|c| c == primary)
+                                {
+                                    paper.categories[..=pos].rotate_right(1);
+                                }
+                                date::normalize(&mut paper);
+                                papers.push(paper);
+                            }
+                        }
+                        (Some("entry"), field) => {
+                            // One entry with an odd id should not fail the whole page
+                            if let Some(paper) = entry.as_mut()
+                                && let Err(e) = Self::apply_field(paper, field, value)
+                            {
+                                eprintln!("Warning: skipping feed entry: {}", e);
+                                entry = None;
+                            }
+                        }
+                        (Some("author"), "name") => {
+                            if let Some(paper) = entry.as_mut() {
+                                paper.authors.push(value);
+                            }
+                        }
+                        _ => {}
+                    }
+                    text.clear();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Feed { total_results, papers })
+    }
+
+    fn apply_field(paper: &mut Paper, field: &str, value: String) -> Result<()> {
+        match field {
+            "id" if value.contains("/api/errors") => {
+                // Example: http://arxiv.org/api/errors#incorrect_id_format_for_foo
+                paper.url = value;
+            }
+            "id" => {
+                // Example: http://arxiv.org/abs/2512.04518v1
+                let id = value.parse::<ArxivId>()?;
+                paper.version = id.version();
+                let id = id.without_version();
+                paper.url = id.abs_url();
+                if paper.pdf_url.is_empty() {
+                    paper.pdf_url = id.pdf_url();
+                }
+                paper.id = id;
+            }
+            "title" => paper.title = value,
+            "summary" => paper.summary = value,
+            "published" => {
+                paper.submitted = date::parse(&value);
+                paper.published_date = value;
+            }
+            "updated" => paper.updated = date::parse(&value),
+            "arxiv:doi" => paper.doi = Some(value),
+            "arxiv:comment" => paper.comments = Some(value),
+            "arxiv:journal_ref" => paper.journal_ref = Some(value),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parse the "Submission history" block of an abstract page
+    ///
+    /// Entries look like `[v1] Mon, 12 Jun 2017 17:57:34 UTC (1,102 KB)`.
+    fn parse_submission_history(html: &str) -> Vec<Version> {
+        let Some(start) = html.find("submission-history") else {
+            return Vec::new();
+        };
+        let block = &html[start..];
+        let block = &block[..block.find("</div>").unwrap_or(block.len())];
+
+        // Drop markup, leaving the text of the block
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in block.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' if in_tag => in_tag = false,
+                _ if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+
+        text.split("[v")
+            .skip(1)
+            .filter_map(|entry| {
+                let (number, rest) = entry.split_once(']')?;
+                let (submitted, rest) = rest.split_once('(')?;
+                let (size, _) = rest.split_once(')')?;
+                Some(Version {
+                    version: number.trim().parse().ok()?,
+                    submitted: submitted.split_whitespace().collect::<Vec<_>>().join(" "),
+                    size_kb: Self::parse_size_kb(size),
+                })
+            })
+            .collect()
+    }
+
+    /// Convert a size such as `1,102 KB` or `2.5 MB` into kilobytes
+    fn parse_size_kb(size: &str) -> Option<u64> {
+        let (number, unit) = size.trim().split_once(' ')?;
+        let number: f64 = number.replace(',', "").parse().ok()?;
+        match unit.trim() {
+            "KB" => Some(number.round() as u64),
+            "MB" => Some((number * 1024.0).round() as u64),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::category::Category;
+    use chrono::NaiveDate;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:opensearch="http://a9.com/-/spec/opensearch/1.1/" xmlns:arxiv="http://arxiv.org/schemas/atom">
+  <title type="html">ArXiv Query: search_query=all:LLM</title>
+  <id>http://arxiv.org/api/cHxbiOdZaP56ODnBPIenZhzg5f8</id>
+  <opensearch:totalResults>1</opensearch:totalResults>
+  <opensearch:startIndex>0</opensearch:startIndex>
+  <entry>
+    <id>http://arxiv.org/abs/1706.03762v7</id>
+    <updated>2023-08-02T00:41:18Z</updated>
+    <published>2017-06-12T17:57:34Z</published>
+    <title>Attention Is All
+      You Need</title>
+    <summary>The dominant sequence transduction models are based on complex
+      recurrent &amp; convolutional neural networks.</summary>
+    <author><name>Ashish Vaswani</name></author>
+    <author><name>Noam Shazeer</name></author>
+    <link href="http://arxiv.org/abs/1706.03762v7" rel="alternate" type="text/html"/>
+    <link title="pdf" href="http://arxiv.org/pdf/1706.03762v7" rel="related" type="application/pdf"/>
+    <arxiv:primary_category term="cs.CL" scheme="http://arxiv.org/schemas/atom"/>
+    <category term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+    <category term="cs.CL" scheme="http://arxiv.org/schemas/atom"/>
+    <arxiv:comment>15 pages, 5 figures</arxiv:comment>
+    <arxiv:doi>10.48550/arXiv.1706.03762</arxiv:doi>
+    <arxiv:journal_ref>Advances in Neural Information Processing Systems 30 (2017)</arxiv:journal_ref>
+  </entry>
+</feed>"#;
+
+    const ERROR_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry>
+    <id>http://arxiv.org/api/errors#incorrect_id_format_for_foo</id>
+    <title>Error</title>
+    <summary>incorrect id format for foo</summary>
+  </entry>
+</feed>"#;
+
+    /// Serve a single canned HTTP response on a local port and return its base URL, along
+    /// with a handle resolving to the request line received
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let read = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..read]);
+            let request_line = request.lines().next().unwrap_or_default().to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/atom+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            request_line
+        });
+        (format!("http://{}/api/query", addr), request)
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let feed = ArxivApi::parse_feed(FEED).unwrap();
+        assert_eq!(feed.total_results, Some(1));
+        assert_eq!(feed.papers.len(), 1);
+
+        let paper = &feed.papers[0];
+        assert_eq!(paper.id, "1706.03762");
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!(paper.authors, vec!["Ashish Vaswani", "Noam Shazeer"]);
+        assert!(paper.summary.contains("recurrent & convolutional"));
+        assert_eq!(paper.published_date, "2017-06-12T17:57:34Z");
+        assert_eq!(paper.submitted, NaiveDate::from_ymd_opt(2017, 6, 12));
+        assert_eq!(paper.updated, NaiveDate::from_ymd_opt(2023, 8, 2));
+        assert_eq!(paper.url, "https://arxiv.org/abs/1706.03762");
+        assert_eq!(paper.pdf_url, "http://arxiv.org/pdf/1706.03762v7");
+        assert_eq!(paper.version, Some(7));
+        assert_eq!(paper.primary_category.as_deref(), Some("cs.CL"));
+        assert_eq!(paper.categories, vec!["cs.CL", "cs.LG"]);
+        assert_eq!(paper.comments.as_deref(), Some("15 pages, 5 figures"));
+        assert_eq!(paper.doi.as_deref(), Some("10.48550/arXiv.1706.03762"));
+        assert_eq!(
+            paper.journal_ref.as_deref(),
+            Some("Advances in Neural Information Processing Systems 30 (2017)")
+        );
+    }
+
+    #[test]
+    fn test_parse_submission_history() {
+        let html = r#"<div class="submission-history">
+    <h2>Submission history</h2> From: Ashish Vaswani [<a href="/show-email/f53b7360/1706.03762">view email</a>]
+    <br/>
+    <strong><a href="/abs/1706.03762v1" rel="nofollow">[v1]</a></strong>
+        Mon, 12 Jun 2017 17:57:34 UTC (1,102 KB)<br/>
+    <strong><a href="/abs/1706.03762v2" rel="nofollow">[v2]</a></strong>
+        Mon, 19 Jun 2017 16:49:45 UTC (2.5 MB)<br/>
+    <strong>[v3]</strong>
+        Wed, 2 Aug 2023 00:41:18 UTC (1,124 KB)<br/>
+</div>
+<div class="extra-services">[v9] not history</div>"#;
+        let versions = ArxivApi::parse_submission_history(html);
+        assert_eq!(
+            versions,
+            vec![
+                Version {
+                    version: 1,
+                    submitted: "Mon, 12 Jun 2017 17:57:34 UTC".to_string(),
+                    size_kb: Some(1102),
+                },
+                Version {
+                    version: 2,
+                    submitted: "Mon, 19 Jun 2017 16:49:45 UTC".to_string(),
+                    size_kb: Some(2560),
+                },
+                Version {
+                    version: 3,
+                    submitted: "Wed, 2 Aug 2023 00:41:18 UTC".to_string(),
+                    size_kb: Some(1124),
+                },
+            ]
+        );
+        assert!(ArxivApi::parse_submission_history("<html></html>").is_empty());
+    }
+
+    #[test]
+    fn test_parse_feed_skips_entries_with_unparseable_ids() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry><id>http://arxiv.org/abs/not-an-id</id><title>Odd</title></entry>
+  <entry><id>http://arxiv.org/abs/2301.00001v1</id><title>Kept</title></entry>
+</feed>"#;
+        let feed = ArxivApi::parse_feed(xml).unwrap();
+        let titles = feed.papers.iter().map(|paper| paper.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, ["Kept"]);
+    }
+
+    #[test]
+    fn test_parse_feed_error_entry() {
+        let err = ArxivApi::parse_feed(ERROR_FEED).unwrap_err();
+        assert!(err.to_string().contains("incorrect id format"));
+    }
+
+    #[test]
+    fn test_build_query_url() {
+        let api = ArxivApi::new(DEFAULT_API_BASE_URL);
+        let url = api.build_query_url(&SearchOptions::new("machine learning"), 100).unwrap();
+        assert_eq!(
+            url,
+            "https://export.arxiv.org/api/query?search_query=all%3Amachine%20AND%20all%3Alearning&start=100&max_results=100&sortBy=relevance&sortOrder=descending"
+        );
+    }
+
+    #[test]
+    fn test_build_query_url_with_dates() {
+        let api = ArxivApi::new(DEFAULT_API_BASE_URL);
+        let url = api.build_query_url(&SearchOptions::new("LLM").after("2023-01-01"), 0).unwrap();
+        assert!(url.contains(
+            &urlencoding::encode("all:LLM AND submittedDate:[202301010000 TO *]").into_owned()
+        ));
+    }
+
+    #[test]
+    fn test_build_query_url_sort_and_date_type() {
+        let api = ArxivApi::new(DEFAULT_API_BASE_URL);
+        let options = SearchOptions::new("LLM")
+            .sort(SortBy::Updated, SortOrder::Ascending)
+            .date_type(DateType::Updated)
+            .before("2024-01-31");
+        let url = api.build_query_url(&options, 0).unwrap();
+        assert!(url.ends_with("&sortBy=lastUpdatedDate&sortOrder=ascending"));
+        assert!(
+            url.contains(&urlencoding::encode("lastUpdatedDate:[* TO 202401312359]").into_owned())
+        );
+
+        let options = options.sort(SortBy::Announced, SortOrder::Descending);
+        assert!(api.build_query_url(&options, 0).is_err());
+    }
+
+    #[test]
+    fn test_build_query_url_with_categories() {
+        let api = ArxivApi::new(DEFAULT_API_BASE_URL);
+        let options = SearchOptions::new("LLM")
+            .category(Category::Subject("cs.CL"))
+            .category(Category::Group("stat"));
+        let url = api.build_query_url(&options, 0).unwrap();
+        assert!(
+            url.contains(
+                &urlencoding::encode("all:LLM AND (cat:cs.CL OR cat:stat.*)").into_owned()
+            )
+        );
+
+        assert!(api.build_query_url(&options.exclude_cross_list(true), 0).is_err());
+    }
+
+    #[test]
+    fn test_build_fetch_url() {
+        let api = ArxivApi::new("http://localhost:8080/api/query/");
+        assert_eq!(
+            api.build_fetch_url(&"2512.04518".parse().unwrap()),
+            "http://localhost:8080/api/query?id_list=2512.04518"
+        );
+        assert_eq!(
+            api.build_fetch_url(&"https://arxiv.org/pdf/2512.04518v2.pdf".parse().unwrap()),
+            "http://localhost:8080/api/query?id_list=2512.04518v2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_page_against_local_server() {
+        let (base_url, _) = serve_once(FEED).await;
+        let api = ArxivApi::new(&base_url);
+        let page = api.search_page(&SearchOptions::new("LLM"), 0).await.unwrap();
+        assert_eq!(page.total, Some(1));
+        assert_eq!(page.papers.len(), 1);
+        assert_eq!(page.papers[0].id, "1706.03762");
+    }
+
+    #[tokio::test]
+    async fn test_versions_read_from_the_api_host() {
+        const HISTORY: &str = r#"<div class="submission-history">
+    <strong><a href="/abs/1706.03762v1">[v1]</a></strong> Mon, 12 Jun 2017 17:57:34 UTC (1,102 KB)
+</div>"#;
+        let (base_url, request) = serve_once(HISTORY).await;
+        let api = ArxivApi::new(&base_url);
+        let versions = api.versions(&"1706.03762v2".parse().unwrap()).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(request.await.unwrap(), "GET /abs/1706.03762 HTTP/1.1");
+
+        let api = ArxivApi::new("https://export.arxiv.org/api/query");
+        assert_eq!(
+            api.build_abs_url(&"hep-th/9901001".parse().unwrap()).unwrap(),
+            "https://export.arxiv.org/abs/hep-th/9901001"
+        );
+    }
+}
@@ -0,0 +1,580 @@
+use super::{ArxivBackend, SearchPage};
+use crate::arxiv_search::{DateType, SearchOptions, SortBy, SortOrder};
+use crate::category::{self, Category};
+use crate::cdp::{CdpBrowser, CdpPage, WaitUntil};
+use crate::config::{Config, Sandbox};
+use crate::date;
+use crate::id::ArxivId;
+use crate::models::Paper;
+use crate::query::{Field, Operator, SearchQuery};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Number of results shown per arXiv search page
+const PAGE_SIZE: usize = 50;
+
+/// arXiv search refuses to paginate beyond this many hits
+const MAX_RESULTS: usize = 10_000;
+
+/// User-agent of a desktop Chrome, used unless `user_agent` is configured
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// How long a search page may take to load
+const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Backend scraping arxiv.org through a Chrome instance driven over CDP
+pub struct BrowserBackend {
+    /// Running browser, replaced by `restart` after a crash
+    browser: RwLock<Arc<CdpBrowser>>,
+    /// Settings used to relaunch the browser
+    config: Config,
+    /// Held while relaunching so concurrent failures restart the browser only once
+    restarting: Mutex<()>,
+    concurrency: usize,
+    /// Minimum delay between starting two page loads
+    request_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+#[async_trait]
+impl ArxivBackend for BrowserBackend {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn max_results(&self) -> Option<usize> {
+        Some(MAX_RESULTS)
+    }
+
+    fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.browser().failure()
+    }
+
+    async fn restart(&self) -> Result<()> {
+        let _restarting = self.restarting.lock().await;
+        // Another request may have relaunched the browser while this one waited
+        if self.failure().is_none() {
+            return Ok(());
+        }
+        let browser = Arc::new(Self::launch_browser(&self.config).await?);
+        *self.browser.write().map_err(|_| anyhow::anyhow!("Browser lock poisoned"))? = browser;
+        Ok(())
+    }
+
+    async fn search_page(&self, options: &SearchOptions, start: usize) -> Result<SearchPage> {
+        let browser = self.browser();
+        let tab = browser.page().await?;
+        self.throttle().await;
+        let page = Self::search_in(&tab, options, start).await?;
+        tab.release();
+        Ok(page)
+    }
+
+    async fn fetch(&self, id: &ArxivId) -> Result<Paper> {
+        let browser = self.browser();
+        let tab = browser.page().await?;
+        self.throttle().await;
+        let paper = Self::fetch_in(&tab, id).await?;
+        tab.release();
+        Ok(paper)
+    }
+}
+
+impl BrowserBackend {
+    async fn search_in(tab: &CdpPage, options: &SearchOptions, start: usize) -> Result<SearchPage> {
+        let url = Self::build_search_url(options, start)?;
+
+        // Results are rendered server-side, so they are in the DOM once it is parsed
+        tab.goto_until(&url, WaitUntil::DomContentLoaded, NAVIGATION_TIMEOUT).await?;
+
+        let check_script = include_str!("../scripts/check_search_results.js");
+        let status = tab.evaluate(check_script).await?;
+        let status = status.as_str().unwrap_or("unknown");
+
+        if status == "abstract" {
+            // Single result from redirect
+            let js_script = include_str!("../scripts/extract_paper.js");
+            let value = tab.evaluate(js_script).await?;
+            let json_str: String = serde_json::from_value(value)?;
+            let paper: Paper = serde_json::from_str(&json_str)?;
+            return Ok(SearchPage { papers: vec![Self::normalize(paper)], total: Some(1) });
+        }
+
+        if status != "found" {
+            // No more results found, or a page without results such as an error page
+            return Ok(SearchPage::default());
+        }
+
+        let total_script = include_str!("../scripts/extract_total_results.js");
+        let total = tab.evaluate(total_script).await?.as_u64().map(|n| n as usize);
+
+        let js_script = include_str!("../scripts/extract_search_results.js");
+
+        let value = tab.evaluate(js_script).await?;
+
+        let json_str: String = serde_json::from_value(value)?;
+        let papers = Self::parse_results(&json_str)?.into_iter().map(Self::normalize).collect();
+
+        Ok(SearchPage { papers, total })
+    }
+
+    async fn fetch_in(tab: &CdpPage, id: &ArxivId) -> Result<Paper> {
+        let url = Self::build_fetch_url(id);
+
+        tab.goto(&url).await?;
+
+        if !tab.wait_for_element("h1.title", 10).await? {
+            return Err(anyhow::anyhow!("Paper page not loaded correctly or timeout"));
+        }
+
+        let js_script = include_str!("../scripts/extract_paper.js");
+
+        let value = tab.evaluate(js_script).await?;
+
+        let json_str: String = serde_json::from_value(value)?;
+        let paper: Paper = serde_json::from_str(&json_str)?;
+        Ok(Self::normalize(paper))
+    }
+
+    /// Wait until `request_interval` has passed since the previous page load started
+    async fn throttle(&self) {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(elapsed) = last_request.map(|t| t.elapsed())
+            && elapsed < self.request_interval
+        {
+            tokio::time::sleep(self.request_interval - elapsed).await;
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    /// The browser currently in use
+    fn browser(&self) -> Arc<CdpBrowser> {
+        self.browser.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub async fn launch(config: &Config) -> Result<Self> {
+        let browser = Self::launch_browser(config).await?;
+        Ok(Self {
+            browser: RwLock::new(Arc::new(browser)),
+            config: config.clone(),
+            restarting: Mutex::new(()),
+            concurrency: config.concurrency.max(1),
+            request_interval: Duration::from_millis(config.request_delay_ms),
+            last_request: Mutex::new(None),
+        })
+    }
+
+    /// Attach to `browser_url` when configured, otherwise launch a Chrome of our own
+    async fn launch_browser(config: &Config) -> Result<CdpBrowser> {
+        if let Some(url) = &config.browser_url {
+            return Ok(CdpBrowser::connect(url).await?.with_page_limit(config.concurrency));
+        }
+        let args = Self::chrome_args(config, running_as_root());
+        let args = args.iter().map(String::as_str).collect();
+        let browser_path = config.browser_path.as_ref().map(std::path::PathBuf::from);
+        let headless = config.headless.then_some(config.headless_mode);
+        Ok(CdpBrowser::launch(browser_path, args, headless, false)
+            .await?
+            .with_page_limit(config.concurrency))
+    }
+
+    /// Chrome arguments for the launch settings of `config`
+    fn chrome_args(config: &Config, as_root: bool) -> Vec<String> {
+        let user_agent = config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let mut args = vec![format!("--user-agent={}", user_agent)];
+        let no_sandbox = match config.sandbox {
+            Sandbox::Auto => as_root,
+            Sandbox::Enabled => false,
+            Sandbox::Disabled => true,
+        };
+        if no_sandbox {
+            args.push("--no-sandbox".to_string());
+        }
+        if config.disable_dev_shm_usage {
+            args.push("--disable-dev-shm-usage".to_string());
+        }
+        if let Some((width, height)) = config.window_size.as_deref().and_then(|s| s.split_once('x'))
+        {
+            args.push(format!("--window-size={},{}", width, height));
+        }
+        args.extend(config.chrome_args.iter().cloned());
+        args
+    }
+
+    fn build_search_url(options: &SearchOptions, start: usize) -> Result<String> {
+        let (after, before) = (&options.after, &options.before);
+        let order = Self::order_param(options.sort_by, options.sort_order)?;
+        match options.query.as_simple() {
+            Some(query) if after.is_none() && before.is_none() && options.categories.is_empty() => {
+                let mut url = format!(
+                    "https://arxiv.org/search/?query={}&searchtype=all&source=header&start={}",
+                    urlencoding::encode(query),
+                    start
+                );
+                if !order.is_empty() {
+                    url.push_str(&format!("&order={}", order));
+                }
+                Ok(url)
+            }
+            _ => {
+                let (date_filter, from_date, to_date) = if after.is_some() || before.is_some() {
+                    ("date_range", after.as_deref().unwrap_or(""), before.as_deref().unwrap_or(""))
+                } else {
+                    ("all_dates", "", "")
+                };
+                Ok(format!(
+                    "https://arxiv.org/search/advanced?advanced=1&{}&{}&date-filter_by={}&date-from_date={}&date-to_date={}&date-date_type={}&abstracts=show&size=50&order={}&start={}",
+                    Self::advanced_query(options)?.to_advanced_params(),
+                    category::advanced_params(&options.categories, options.exclude_cross_list)?,
+                    date_filter,
+                    from_date,
+                    to_date,
+                    Self::date_type_param(options.date_type),
+                    order,
+                    start
+                ))
+            }
+        }
+    }
+
+    /// Value of the `order` parameter; empty means relevance
+    fn order_param(sort_by: SortBy, sort_order: SortOrder) -> Result<String> {
+        let key = match sort_by {
+            SortBy::Relevance => return Ok(String::new()),
+            SortBy::Submitted => "submitted_date",
+            SortBy::Announced => "announced_date_first",
+            SortBy::Updated => {
+                return Err(anyhow::anyhow!(
+                    "Sorting by update date is not supported by the browser backend"
+                ));
+            }
+        };
+        Ok(match sort_order {
+            SortOrder::Ascending => key.to_string(),
+            SortOrder::Descending => format!("-{}", key),
+        })
+    }
+
+    fn date_type_param(date_type: DateType) -> &'static str {
+        match date_type {
+            DateType::Submitted => "submitted_date_first",
+            DateType::Updated => "submitted_date",
+            DateType::Announced => "announced_date_first",
+        }
+    }
+
+    /// Prepend subject class filters, which the advanced form only supports as search terms
+    ///
+    /// The form evaluates terms left to right, so `cs.CL AND a OR b` means `(cs.CL AND a) OR b`
+    /// and the filter would not apply to `b`; OR terms are rejected together with subject
+    /// classes. Subject terms also match cross-lists, so they cannot honour
+    /// `exclude_cross_list`.
+    fn advanced_query(options: &SearchOptions) -> Result<SearchQuery> {
+        let subjects = options
+            .categories
+            .iter()
+            .filter(|c| matches!(c, Category::Subject(_)))
+            .collect::<Vec<_>>();
+        if let Some(subject) = subjects.first() {
+            if options.exclude_cross_list {
+                return Err(anyhow::anyhow!(
+                    "Excluding cross-lists is not supported for subject classes such as {} by \
+                     the browser backend",
+                    subject.code()
+                ));
+            }
+            if options.query.terms().iter().skip(1).any(|term| term.operator == Operator::Or) {
+                return Err(anyhow::anyhow!(
+                    "OR terms cannot be combined with subject classes such as {} by the browser \
+                     backend",
+                    subject.code()
+                ));
+            }
+        }
+
+        let mut query = SearchQuery::new();
+        for (i, subject) in subjects.into_iter().enumerate() {
+            let operator = if i == 0 { Operator::And } else { Operator::Or };
+            query = query.push(operator, Field::Category, subject.code());
+        }
+        for (i, term) in options.query.terms().iter().enumerate() {
+            let operator = if i == 0 { Operator::And } else { term.operator };
+            query = query.push(operator, term.field, term.value.clone());
+        }
+        Ok(query)
+    }
+
+    /// Papers extracted from a results page, skipping with a warning any entry that does not
+    /// parse, such as one with an unrecognised id, rather than failing the whole page
+    fn parse_results(json: &str) -> Result<Vec<Paper>> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+        let papers = entries.into_iter().filter_map(|entry| {
+            let id = entry.get("id").cloned().unwrap_or_default();
+            let mut paper: Paper = serde_json::from_value(entry)
+                .inspect_err(|e| eprintln!("Warning: skipping search result {}: {}", id, e))
+                .ok()?;
+            paper.summary = Self::clean_summary(&paper.summary);
+            Some(paper)
+        });
+        Ok(papers.collect())
+    }
+
+    /// Abstract text of a search result without the `Abstract:` label, the "△ Less" or
+    /// "▽ More" toggle and the indentation of the page source
+    fn clean_summary(text: &str) -> String {
+        let mut words = text.split_whitespace().collect::<Vec<_>>();
+        if words.first() == Some(&"Abstract:") {
+            words.remove(0);
+        }
+        if let [.., arrow, label] = words[..]
+            && matches!((arrow, label), ("▽" | "▼", "More") | ("△" | "▲", "Less"))
+        {
+            words.truncate(words.len() - 2);
+        }
+        words.join(" ")
+    }
+
+    fn build_fetch_url(id: &ArxivId) -> String {
+        id.abs_url()
+    }
+
+    /// Move a version from the page URL into `Paper::version`, falling back to the latest
+    /// version of the submission history, and parse the dates shown on the page
+    fn normalize(mut paper: Paper) -> Paper {
+        paper.version =
+            paper.id.version().or_else(|| paper.versions.iter().map(|v| v.version).max());
+        paper.id = paper.id.without_version();
+        date::normalize(&mut paper);
+        paper
+    }
+}
+
+/// Whether the process runs as root, where Chrome refuses to start with its sandbox
+fn running_as_root() -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // `/proc/self` belongs to the effective user; without procfs (macOS) assume not root
+        std::fs::metadata("/proc/self").is_ok_and(|meta| meta.uid() == 0)
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_args() {
+        let config = Config::default();
+        let args = BrowserBackend::chrome_args(&config, false);
+        assert_eq!(args, [format!("--user-agent={}", DEFAULT_USER_AGENT)]);
+        // The sandbox is dropped automatically for root, as Chrome will not start otherwise
+        assert!(BrowserBackend::chrome_args(&config, true).contains(&"--no-sandbox".to_string()));
+
+        let config = Config {
+            sandbox: Sandbox::Enabled,
+            disable_dev_shm_usage: true,
+            window_size: Some("1280x800".to_string()),
+            user_agent: Some("arxiv-cli/1.0".to_string()),
+            chrome_args: vec!["--lang=en-US".to_string()],
+            ..Config::default()
+        };
+        assert_eq!(
+            BrowserBackend::chrome_args(&config, true),
+            [
+                "--user-agent=arxiv-cli/1.0",
+                "--disable-dev-shm-usage",
+                "--window-size=1280,800",
+                "--lang=en-US"
+            ]
+        );
+        let config = Config { sandbox: Sandbox::Disabled, ..Config::default() };
+        assert!(BrowserBackend::chrome_args(&config, false).contains(&"--no-sandbox".to_string()));
+    }
+
+    #[test]
+    fn test_build_search_url_simple() {
+        let url = BrowserBackend::build_search_url(&SearchOptions::new("LLM"), 0).unwrap();
+        assert_eq!(url, "https://arxiv.org/search/?query=LLM&searchtype=all&source=header&start=0");
+    }
+
+    #[test]
+    fn test_build_search_url_with_pagination() {
+        let url = BrowserBackend::build_search_url(&SearchOptions::new("LLM"), 50).unwrap();
+        assert_eq!(
+            url,
+            "https://arxiv.org/search/?query=LLM&searchtype=all&source=header&start=50"
+        );
+    }
+
+    #[test]
+    fn test_build_search_url_with_dates() {
+        let options = SearchOptions::new("LLM").after("2023-01-01").before("2023-12-31");
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.contains("date-filter_by=date_range"));
+        assert!(url.contains("date-from_date=2023-01-01"));
+        assert!(url.contains("date-to_date=2023-12-31"));
+    }
+
+    #[test]
+    fn test_build_search_url_sort_and_date_type() {
+        let options = SearchOptions::new("LLM").sort(SortBy::Submitted, SortOrder::Ascending);
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.starts_with("https://arxiv.org/search/?query=LLM"));
+        assert!(url.ends_with("&order=submitted_date"));
+
+        let options = options
+            .sort(SortBy::Announced, SortOrder::Descending)
+            .date_type(DateType::Updated)
+            .after("2023-01-01");
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.contains("&order=-announced_date_first&"));
+        assert!(url.contains("date-date_type=submitted_date&"));
+
+        let options = SearchOptions::new("LLM").after("2023-01-01");
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.contains("&order=&"));
+        assert!(url.contains("date-date_type=submitted_date_first&"));
+
+        let options = SearchOptions::new("LLM").sort(SortBy::Updated, SortOrder::Descending);
+        assert!(BrowserBackend::build_search_url(&options, 0).is_err());
+    }
+
+    #[test]
+    fn test_build_fetch_url_id() {
+        let url = BrowserBackend::build_fetch_url(&"2512.04518".parse().unwrap());
+        assert_eq!(url, "https://arxiv.org/abs/2512.04518");
+    }
+
+    #[test]
+    fn test_build_fetch_url_full_url() {
+        let url =
+            BrowserBackend::build_fetch_url(&"https://arxiv.org/abs/2512.04518".parse().unwrap());
+        assert_eq!(url, "https://arxiv.org/abs/2512.04518");
+    }
+
+    #[test]
+    fn test_build_search_url_with_before_only() {
+        let options = SearchOptions::new("conversational data analysis").before("2023-10-13");
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.contains("date-filter_by=date_range"));
+        assert!(url.contains("date-from_date=&"));
+        assert!(url.contains("date-to_date=2023-10-13"));
+        assert!(url.contains("terms-0-term=conversational%20data%20analysis"));
+    }
+
+    #[test]
+    fn test_build_search_url_with_fielded_terms() {
+        let query = SearchQuery::new()
+            .and(Field::Title, "attention")
+            .and(Field::Author, "Vaswani")
+            .not(Field::All, "recurrent");
+        let url = BrowserBackend::build_search_url(&SearchOptions::new(query), 0).unwrap();
+        assert!(url.starts_with("https://arxiv.org/search/advanced?advanced=1&"));
+        assert!(url.contains("terms-0-term=attention&terms-0-field=title"));
+        assert!(url.contains("terms-1-operator=AND&terms-1-term=Vaswani&terms-1-field=author"));
+        assert!(url.contains("terms-2-operator=NOT&terms-2-term=recurrent&terms-2-field=all"));
+        assert!(url.contains("date-filter_by=all_dates"));
+    }
+
+    #[test]
+    fn test_build_search_url_with_categories() {
+        let options = SearchOptions::new("LLM")
+            .category(Category::Group("cs"))
+            .category(Category::Subject("stat.ML"))
+            .category(Category::Subject("cs.CL"));
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.starts_with("https://arxiv.org/search/advanced?"));
+        assert!(url.contains(
+            "terms-0-operator=AND&terms-0-term=stat.ML&terms-0-field=cross_list_category"
+        ));
+        assert!(
+            url.contains(
+                "terms-1-operator=OR&terms-1-term=cs.CL&terms-1-field=cross_list_category"
+            )
+        );
+        assert!(url.contains("terms-2-operator=AND&terms-2-term=LLM&terms-2-field=all"));
+        assert!(url.contains("classification-computer_science=y"));
+        assert!(url.contains("classification-include_cross_list=include"));
+
+        let options = SearchOptions::new("LLM").category(Category::Group("cs"));
+        let url = BrowserBackend::build_search_url(&options.exclude_cross_list(true), 0).unwrap();
+        assert!(url.contains("classification-include_cross_list=exclude"));
+    }
+
+    #[test]
+    fn test_build_search_url_rejects_subject_with_or_or_cross_list_exclusion() {
+        let query = SearchQuery::new().and(Field::Title, "a").or(Field::Author, "b");
+        let options = SearchOptions::new(query).category(Category::Subject("cs.CL"));
+        let error = BrowserBackend::build_search_url(&options, 0).unwrap_err();
+        assert!(error.to_string().contains("OR terms"), "{}", error);
+
+        let options = SearchOptions::new("LLM").category(Category::Subject("cs.CL"));
+        assert!(BrowserBackend::build_search_url(&options.exclude_cross_list(true), 0).is_err());
+
+        // AND and NOT terms keep the filter on every term when evaluated left to right
+        let query = SearchQuery::new().and(Field::Title, "a").not(Field::Author, "b");
+        let options = SearchOptions::new(query).category(Category::Subject("cs.CL"));
+        let url = BrowserBackend::build_search_url(&options, 0).unwrap();
+        assert!(url.contains("terms-2-operator=NOT&terms-2-term=b&terms-2-field=author"));
+
+        // Without subject classes OR terms are passed through as before
+        let query = SearchQuery::new().and(Field::Title, "a").or(Field::Author, "b");
+        let options = SearchOptions::new(query).category(Category::Group("cs"));
+        assert!(BrowserBackend::build_search_url(&options, 0).is_ok());
+    }
+
+    #[test]
+    fn test_parse_results_skips_unparseable_entries() {
+        let entry = |id: &str, title: &str| {
+            serde_json::json!({
+                "id": id, "title": title, "authors": [], "summary": "", "published_date": "",
+                "url": "", "pdf_url": "",
+            })
+        };
+        let json = serde_json::json!([
+            entry("2301.00001", "Kept"),
+            entry("", "Missing id"),
+            entry("2301.00002v2", "Also kept"),
+        ]);
+        let papers = BrowserBackend::parse_results(&json.to_string()).unwrap();
+        let titles = papers.iter().map(|paper| paper.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, ["Kept", "Also kept"]);
+
+        assert!(BrowserBackend::parse_results("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_results_cleans_abstracts() {
+        // `textContent` of an `.abstract-full` whose toggle link was not removed
+        const ABSTRACT: &str = "
+        Abstract: The dominant sequence transduction models are based on complex recurrent or
+        convolutional neural networks.   We propose the Transformer.
+        △ Less
+      ";
+        let json = serde_json::json!([{
+            "id": "1706.03762", "title": "Attention Is All You Need", "authors": [],
+            "summary": ABSTRACT, "published_date": "", "url": "", "pdf_url": "",
+        }]);
+        let papers = BrowserBackend::parse_results(&json.to_string()).unwrap();
+        assert_eq!(
+            papers[0].summary,
+            "The dominant sequence transduction models are based on complex recurrent or \
+             convolutional neural networks. We propose the Transformer."
+        );
+
+        assert_eq!(BrowserBackend::clean_summary("Short ▽ More"), "Short");
+        assert_eq!(BrowserBackend::clean_summary("Ends in More"), "Ends in More");
+    }
+}
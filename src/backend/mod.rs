@@ -0,0 +1,76 @@
+//! Transports used by [`ArxivClient`](crate::ArxivClient) to talk to arXiv
+
+mod api;
+#[cfg(feature = "browser")]
+mod browser;
+
+pub use api::{ArxivApi, DEFAULT_API_BASE_URL};
+#[cfg(feature = "browser")]
+pub use browser::BrowserBackend;
+
+use crate::arxiv_search::SearchOptions;
+use crate::id::ArxivId;
+use crate::models::{Paper, Version};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+/// One page of search results as returned by a backend
+#[derive(Debug, Default)]
+pub struct SearchPage {
+    pub papers: Vec<Paper>,
+    /// Total number of hits for the query, when the backend reports it
+    pub total: Option<usize>,
+}
+
+/// Transport used by `ArxivClient` to talk to arXiv
+///
+/// Implementations only deal with single requests; pagination, limits and PDF text extraction
+/// are handled by the client so every backend behaves the same.
+#[async_trait]
+pub trait ArxivBackend: Send + Sync {
+    /// Number of results requested per search page
+    fn page_size(&self) -> usize;
+
+    /// Largest offset the backend can page through for a single query, if capped
+    fn max_results(&self) -> Option<usize> {
+        None
+    }
+
+    /// Number of search pages that may be requested at the same time
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    /// Why the transport stopped working, or `None` while it is healthy
+    ///
+    /// Checked after a failed request: when it reports a failure the client calls
+    /// [`restart`](Self::restart) and retries the request once.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+
+    /// Replace a failed transport, e.g. relaunch a crashed browser
+    async fn restart(&self) -> Result<()> {
+        Err(anyhow!("This backend cannot be restarted"))
+    }
+
+    /// Fetch the page of search results starting at offset `start`
+    async fn search_page(&self, options: &SearchOptions, start: usize) -> Result<SearchPage>;
+
+    /// Fetch the metadata of a single paper, at the requested version or the latest one
+    async fn fetch(&self, id: &ArxivId) -> Result<Paper>;
+
+    /// List the submission history of a paper, oldest version first
+    async fn versions(&self, id: &ArxivId) -> Result<Vec<Version>> {
+        Ok(self.fetch(&id.without_version()).await?.versions)
+    }
+
+    /// Download the PDF of a paper
+    async fn fetch_pdf(&self, paper: &Paper) -> Result<Vec<u8>> {
+        let response = reqwest::get(&paper.pdf_url).await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to download PDF: Status {}", response.status()));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
@@ -0,0 +1,393 @@
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::str::FromStr;
+
+/// Top-level subject groups: (code, advanced search form key, name)
+const GROUPS: &[(&str, &str, &str)] = &[
+    ("cs", "computer_science", "Computer Science"),
+    ("econ", "economics", "Economics"),
+    ("eess", "eess", "Electrical Engineering and Systems Science"),
+    ("math", "mathematics", "Mathematics"),
+    ("physics", "physics", "Physics"),
+    ("q-bio", "q_biology", "Quantitative Biology"),
+    ("q-fin", "q_finance", "Quantitative Finance"),
+    ("stat", "statistics", "Statistics"),
+];
+
+/// Archives of the physics group selectable in the advanced search form
+const PHYSICS_ARCHIVES: &[(&str, &str)] = &[
+    ("astro-ph", "Astrophysics"),
+    ("cond-mat", "Condensed Matter"),
+    ("gr-qc", "General Relativity and Quantum Cosmology"),
+    ("hep-ex", "High Energy Physics - Experiment"),
+    ("hep-lat", "High Energy Physics - Lattice"),
+    ("hep-ph", "High Energy Physics - Phenomenology"),
+    ("hep-th", "High Energy Physics - Theory"),
+    ("math-ph", "Mathematical Physics"),
+    ("nlin", "Nonlinear Sciences"),
+    ("nucl-ex", "Nuclear Experiment"),
+    ("nucl-th", "Nuclear Theory"),
+    ("physics", "Physics"),
+    ("quant-ph", "Quantum Physics"),
+];
+
+/// arXiv category taxonomy: (code, name)
+pub const CATEGORIES: &[(&str, &str)] = &[
+    ("astro-ph.CO", "Cosmology and Nongalactic Astrophysics"),
+    ("astro-ph.EP", "Earth and Planetary Astrophysics"),
+    ("astro-ph.GA", "Astrophysics of Galaxies"),
+    ("astro-ph.HE", "High Energy Astrophysical Phenomena"),
+    ("astro-ph.IM", "Instrumentation and Methods for Astrophysics"),
+    ("astro-ph.SR", "Solar and Stellar Astrophysics"),
+    ("cond-mat.dis-nn", "Disordered Systems and Neural Networks"),
+    ("cond-mat.mes-hall", "Mesoscale and Nanoscale Physics"),
+    ("cond-mat.mtrl-sci", "Materials Science"),
+    ("cond-mat.other", "Other Condensed Matter"),
+    ("cond-mat.quant-gas", "Quantum Gases"),
+    ("cond-mat.soft", "Soft Condensed Matter"),
+    ("cond-mat.stat-mech", "Statistical Mechanics"),
+    ("cond-mat.str-el", "Strongly Correlated Electrons"),
+    ("cond-mat.supr-con", "Superconductivity"),
+    ("cs.AI", "Artificial Intelligence"),
+    ("cs.AR", "Hardware Architecture"),
+    ("cs.CC", "Computational Complexity"),
+    ("cs.CE", "Computational Engineering, Finance, and Science"),
+    ("cs.CG", "Computational Geometry"),
+    ("cs.CL", "Computation and Language"),
+    ("cs.CR", "Cryptography and Security"),
+    ("cs.CV", "Computer Vision and Pattern Recognition"),
+    ("cs.CY", "Computers and Society"),
+    ("cs.DB", "Databases"),
+    ("cs.DC", "Distributed, Parallel, and Cluster Computing"),
+    ("cs.DL", "Digital Libraries"),
+    ("cs.DM", "Discrete Mathematics"),
+    ("cs.DS", "Data Structures and Algorithms"),
+    ("cs.ET", "Emerging Technologies"),
+    ("cs.FL", "Formal Languages and Automata Theory"),
+    ("cs.GL", "General Literature"),
+    ("cs.GR", "Graphics"),
+    ("cs.GT", "Computer Science and Game Theory"),
+    ("cs.HC", "Human-Computer Interaction"),
+    ("cs.IR", "Information Retrieval"),
+    ("cs.IT", "Information Theory"),
+    ("cs.LG", "Machine Learning"),
+    ("cs.LO", "Logic in Computer Science"),
+    ("cs.MA", "Multiagent Systems"),
+    ("cs.MM", "Multimedia"),
+    ("cs.MS", "Mathematical Software"),
+    ("cs.NA", "Numerical Analysis"),
+    ("cs.NE", "Neural and Evolutionary Computing"),
+    ("cs.NI", "Networking and Internet Architecture"),
+    ("cs.OH", "Other Computer Science"),
+    ("cs.OS", "Operating Systems"),
+    ("cs.PF", "Performance"),
+    ("cs.PL", "Programming Languages"),
+    ("cs.RO", "Robotics"),
+    ("cs.SC", "Symbolic Computation"),
+    ("cs.SD", "Sound"),
+    ("cs.SE", "Software Engineering"),
+    ("cs.SI", "Social and Information Networks"),
+    ("cs.SY", "Systems and Control"),
+    ("econ.EM", "Econometrics"),
+    ("econ.GN", "General Economics"),
+    ("econ.TH", "Theoretical Economics"),
+    ("eess.AS", "Audio and Speech Processing"),
+    ("eess.IV", "Image and Video Processing"),
+    ("eess.SP", "Signal Processing"),
+    ("eess.SY", "Systems and Control"),
+    ("gr-qc", "General Relativity and Quantum Cosmology"),
+    ("hep-ex", "High Energy Physics - Experiment"),
+    ("hep-lat", "High Energy Physics - Lattice"),
+    ("hep-ph", "High Energy Physics - Phenomenology"),
+    ("hep-th", "High Energy Physics - Theory"),
+    ("math-ph", "Mathematical Physics"),
+    ("math.AC", "Commutative Algebra"),
+    ("math.AG", "Algebraic Geometry"),
+    ("math.AP", "Analysis of PDEs"),
+    ("math.AT", "Algebraic Topology"),
+    ("math.CA", "Classical Analysis and ODEs"),
+    ("math.CO", "Combinatorics"),
+    ("math.CT", "Category Theory"),
+    ("math.CV", "Complex Variables"),
+    ("math.DG", "Differential Geometry"),
+    ("math.DS", "Dynamical Systems"),
+    ("math.FA", "Functional Analysis"),
+    ("math.GM", "General Mathematics"),
+    ("math.GN", "General Topology"),
+    ("math.GR", "Group Theory"),
+    ("math.GT", "Geometric Topology"),
+    ("math.HO", "History and Overview"),
+    ("math.IT", "Information Theory"),
+    ("math.KT", "K-Theory and Homology"),
+    ("math.LO", "Logic"),
+    ("math.MG", "Metric Geometry"),
+    ("math.MP", "Mathematical Physics"),
+    ("math.NA", "Numerical Analysis"),
+    ("math.NT", "Number Theory"),
+    ("math.OA", "Operator Algebras"),
+    ("math.OC", "Optimization and Control"),
+    ("math.PR", "Probability"),
+    ("math.QA", "Quantum Algebra"),
+    ("math.RA", "Rings and Algebras"),
+    ("math.RT", "Representation Theory"),
+    ("math.SG", "Symplectic Geometry"),
+    ("math.SP", "Spectral Theory"),
+    ("math.ST", "Statistics Theory"),
+    ("nlin.AO", "Adaptation and Self-Organizing Systems"),
+    ("nlin.CD", "Chaotic Dynamics"),
+    ("nlin.CG", "Cellular Automata and Lattice Gases"),
+    ("nlin.PS", "Pattern Formation and Solitons"),
+    ("nlin.SI", "Exactly Solvable and Integrable Systems"),
+    ("nucl-ex", "Nuclear Experiment"),
+    ("nucl-th", "Nuclear Theory"),
+    ("physics.acc-ph", "Accelerator Physics"),
+    ("physics.ao-ph", "Atmospheric and Oceanic Physics"),
+    ("physics.app-ph", "Applied Physics"),
+    ("physics.atm-clus", "Atomic and Molecular Clusters"),
+    ("physics.atom-ph", "Atomic Physics"),
+    ("physics.bio-ph", "Biological Physics"),
+    ("physics.chem-ph", "Chemical Physics"),
+    ("physics.class-ph", "Classical Physics"),
+    ("physics.comp-ph", "Computational Physics"),
+    ("physics.data-an", "Data Analysis, Statistics and Probability"),
+    ("physics.ed-ph", "Physics Education"),
+    ("physics.flu-dyn", "Fluid Dynamics"),
+    ("physics.gen-ph", "General Physics"),
+    ("physics.geo-ph", "Geophysics"),
+    ("physics.hist-ph", "History and Philosophy of Physics"),
+    ("physics.ins-det", "Instrumentation and Detectors"),
+    ("physics.med-ph", "Medical Physics"),
+    ("physics.optics", "Optics"),
+    ("physics.plasm-ph", "Plasma Physics"),
+    ("physics.pop-ph", "Popular Physics"),
+    ("physics.soc-ph", "Physics and Society"),
+    ("physics.space-ph", "Space Physics"),
+    ("q-bio.BM", "Biomolecules"),
+    ("q-bio.CB", "Cell Behavior"),
+    ("q-bio.GN", "Genomics"),
+    ("q-bio.MN", "Molecular Networks"),
+    ("q-bio.NC", "Neurons and Cognition"),
+    ("q-bio.OT", "Other Quantitative Biology"),
+    ("q-bio.PE", "Populations and Evolution"),
+    ("q-bio.QM", "Quantitative Methods"),
+    ("q-bio.SC", "Subcellular Processes"),
+    ("q-bio.TO", "Tissues and Organs"),
+    ("q-fin.CP", "Computational Finance"),
+    ("q-fin.EC", "Economics"),
+    ("q-fin.GN", "General Finance"),
+    ("q-fin.MF", "Mathematical Finance"),
+    ("q-fin.PM", "Portfolio Management"),
+    ("q-fin.PR", "Pricing of Securities"),
+    ("q-fin.RM", "Risk Management"),
+    ("q-fin.ST", "Statistical Finance"),
+    ("q-fin.TR", "Trading and Market Microstructure"),
+    ("quant-ph", "Quantum Physics"),
+    ("stat.AP", "Applications"),
+    ("stat.CO", "Computation"),
+    ("stat.ME", "Methodology"),
+    ("stat.ML", "Machine Learning"),
+    ("stat.OT", "Other Statistics"),
+    ("stat.TH", "Statistics Theory"),
+];
+
+/// Subject filter for searches, validated against the arXiv taxonomy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Top-level group such as `cs` or `physics`
+    Group(&'static str),
+    /// Physics archive such as `astro-ph` or `hep-th`
+    PhysicsArchive(&'static str),
+    /// Subject class such as `cs.CL`
+    Subject(&'static str),
+}
+
+impl Category {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Category::Group(code) | Category::PhysicsArchive(code) | Category::Subject(code) => {
+                code
+            }
+        }
+    }
+
+    /// Human readable name from the taxonomy
+    pub fn name(&self) -> &'static str {
+        let code = self.code();
+        match self {
+            Category::Group(_) => GROUPS.iter().find(|g| g.0 == code).map(|g| g.2),
+            Category::PhysicsArchive(_) => {
+                PHYSICS_ARCHIVES.iter().find(|a| a.0 == code).map(|a| a.1)
+            }
+            Category::Subject(_) => CATEGORIES.iter().find(|c| c.0 == code).map(|c| c.1),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Pattern matching this category in the `cat:` field of the Atom API
+    fn api_pattern(&self) -> String {
+        match self {
+            Category::Group("physics") => format!(
+                "({})",
+                PHYSICS_ARCHIVES
+                    .iter()
+                    .map(|(code, _)| Category::PhysicsArchive(code).api_pattern())
+                    .collect::<Vec<_>>()
+                    .join(" OR ")
+            ),
+            Category::Group(code) => format!("cat:{}.*", code),
+            Category::PhysicsArchive(code) if Self::has_subjects(code) => format!("cat:{}*", code),
+            Category::PhysicsArchive(code) | Category::Subject(code) => format!("cat:{}", code),
+        }
+    }
+
+    fn has_subjects(archive: &str) -> bool {
+        CATEGORIES
+            .iter()
+            .any(|(code, _)| code.strip_prefix(archive).is_some_and(|r| r.starts_with('.')))
+    }
+
+    /// Closest taxonomy codes to a misspelled category
+    fn suggestions(input: &str) -> Vec<&'static str> {
+        let input = input.to_ascii_lowercase();
+        let mut scored = GROUPS
+            .iter()
+            .map(|g| g.0)
+            .chain(PHYSICS_ARCHIVES.iter().map(|a| a.0))
+            .chain(CATEGORIES.iter().map(|c| c.0))
+            .map(|code| (strsim::jaro_winkler(&input, &code.to_ascii_lowercase()), code))
+            .filter(|(score, _)| *score >= 0.8)
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.dedup_by_key(|(_, code)| *code);
+        scored.into_iter().take(3).map(|(_, code)| code).collect()
+    }
+}
+
+impl FromStr for Category {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let eq = |code: &&str| code.eq_ignore_ascii_case(s);
+
+        if let Some(group) = GROUPS.iter().map(|g| g.0).find(eq) {
+            return Ok(Category::Group(group));
+        }
+        if let Some(archive) = PHYSICS_ARCHIVES.iter().map(|a| a.0).find(eq) {
+            return Ok(Category::PhysicsArchive(archive));
+        }
+        if let Some(subject) = CATEGORIES.iter().map(|c| c.0).find(eq) {
+            return Ok(Category::Subject(subject));
+        }
+
+        let suggestions = Self::suggestions(s);
+        if suggestions.is_empty() {
+            Err(anyhow!("Unknown arXiv category: {}", s))
+        } else {
+            Err(anyhow!("Unknown arXiv category: {} (did you mean {}?)", s, suggestions.join(", ")))
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Compile group and physics archive filters into the `classification-*` parameters of the
+/// advanced search form
+///
+/// Subject classes cannot be expressed there and are matched as search terms instead.
+pub fn advanced_params(categories: &[Category], exclude_cross_list: bool) -> Result<String> {
+    let archives =
+        categories.iter().filter(|c| matches!(c, Category::PhysicsArchive(_))).collect::<Vec<_>>();
+    if archives.len() > 1 {
+        return Err(anyhow!(
+            "Only one physics archive can be selected at a time, got {}",
+            archives.iter().map(|c| c.code()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let mut params = Vec::new();
+    for (code, form_key, _) in GROUPS {
+        let selected = categories.iter().any(|c| match c {
+            Category::Group(g) => g == code,
+            Category::PhysicsArchive(_) => *code == "physics",
+            Category::Subject(_) => false,
+        });
+        if selected {
+            params.push(format!("classification-{}=y", form_key));
+        }
+    }
+    params.push(format!(
+        "classification-physics_archives={}",
+        archives.first().map_or("all", |c| c.code())
+    ));
+    params.push(format!(
+        "classification-include_cross_list={}",
+        if exclude_cross_list { "exclude" } else { "include" }
+    ));
+    Ok(params.join("&"))
+}
+
+/// Compile the filters into a `cat:` clause of the Atom API, `None` when there is no filter
+pub fn api_query(categories: &[Category]) -> Option<String> {
+    match categories {
+        [] => None,
+        [category] => Some(category.api_pattern()),
+        _ => Some(format!(
+            "({})",
+            categories.iter().map(Category::api_pattern).collect::<Vec<_>>().join(" OR ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_category() {
+        assert_eq!("cs".parse::<Category>().unwrap(), Category::Group("cs"));
+        assert_eq!("hep-th".parse::<Category>().unwrap(), Category::PhysicsArchive("hep-th"));
+        assert_eq!("cs.cl".parse::<Category>().unwrap(), Category::Subject("cs.CL"));
+        assert_eq!("cs.CL".parse::<Category>().unwrap().name(), "Computation and Language");
+    }
+
+    #[test]
+    fn test_parse_category_suggestions() {
+        let err = "cs.CLL".parse::<Category>().unwrap_err().to_string();
+        assert!(err.contains("did you mean cs.CL"), "{}", err);
+
+        let err = "zzzz".parse::<Category>().unwrap_err().to_string();
+        assert_eq!(err, "Unknown arXiv category: zzzz");
+    }
+
+    #[test]
+    fn test_advanced_params() {
+        let categories = vec![Category::Group("cs"), Category::PhysicsArchive("hep-th")];
+        assert_eq!(
+            advanced_params(&categories, true).unwrap(),
+            "classification-computer_science=y&classification-physics=y&\
+             classification-physics_archives=hep-th&classification-include_cross_list=exclude"
+        );
+        assert_eq!(
+            advanced_params(&[], false).unwrap(),
+            "classification-physics_archives=all&classification-include_cross_list=include"
+        );
+
+        let archives = vec![Category::PhysicsArchive("hep-th"), Category::PhysicsArchive("hep-ph")];
+        assert!(advanced_params(&archives, false).is_err());
+    }
+
+    #[test]
+    fn test_api_query() {
+        assert_eq!(api_query(&[]), None);
+        assert_eq!(api_query(&[Category::Subject("cs.CL")]).unwrap(), "cat:cs.CL");
+        assert_eq!(
+            api_query(&[Category::Group("cs"), Category::PhysicsArchive("astro-ph")]).unwrap(),
+            "(cat:cs.* OR cat:astro-ph*)"
+        );
+    }
+}
@@ -1,173 +1,242 @@
 use anyhow::{Result, anyhow};
-use serde_json::Value;
-use std::io::BufReader;
+use serde_json::{Value, json};
+use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::Semaphore;
 use tokio::time::sleep;
 
+use super::connection::CdpConnection;
+use super::discovery;
+use super::page::CdpPage;
+use super::pool::PooledPage;
+use super::profile::{self, Profile};
+use crate::config::HeadlessMode;
+
+/// Tabs open at the same time unless changed with [`CdpBrowser::with_page_limit`]
+const DEFAULT_PAGE_LIMIT: usize = 4;
+
+/// How long a launched Chrome may take to open its DevTools port
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How long dropping a launched Chrome waits for it to exit
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Chrome browser process manager
+///
+/// Either launches its own Chrome, which is killed and its profile deleted on drop, or attaches
+/// to a running one with [`CdpBrowser::connect`], which is left running.
 pub struct CdpBrowser {
-    process: Option<Child>,
-    port: u16,
-    #[allow(dead_code)]
-    ws_url: String,
+    /// The Chrome process and its profile, only when this browser launched it
+    process: Mutex<Option<Launched>>,
+    /// `host:port` of the DevTools endpoint, used to reach the tabs
+    endpoint: String,
+    /// Browser-level connection used to open and close tabs
+    connection: Arc<CdpConnection>,
+    /// Tabs kept open between requests
+    idle: Mutex<Vec<CdpPage>>,
+    /// Bounds the number of tabs handed out by `page`
+    page_permits: Semaphore,
 }
 
 impl CdpBrowser {
+    /// Launch Chrome with a throwaway profile, headless unless `headless` is `None`
+    ///
+    /// Without `executable_path` the browser is picked by [`discovery::find`].
     pub async fn launch(
         executable_path: Option<PathBuf>,
         args: Vec<&str>,
-        headless: bool,
+        headless: Option<HeadlessMode>,
         debug: bool,
     ) -> Result<Self> {
-        let chrome_path = executable_path.unwrap_or_else(|| {
-            #[cfg(target_os = "windows")]
-            {
-                PathBuf::from("C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe")
-            }
-            #[cfg(target_os = "macos")]
-            {
-                PathBuf::from("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome")
-            }
-            #[cfg(target_os = "linux")]
-            {
-                PathBuf::from("/usr/bin/google-chrome")
-            }
-            #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-            {
-                PathBuf::from("chrome")
-            }
-        });
-
-        // Create a temporary user data directory with a unique ID
+        let chrome_path = match executable_path {
+            Some(path) => path,
+            None => discovery::find()?.path,
+        };
 
-        // Create a temporary user data directory with a unique ID
-        let unique_id = uuid::Uuid::new_v4();
-        let temp_dir = std::env::temp_dir().join(format!("chrome-{}", unique_id));
-        std::fs::create_dir_all(&temp_dir)?;
+        // Profiles of earlier runs that crashed or were killed are never cleaned up otherwise
+        profile::sweep();
+        let profile = Profile::create()?;
 
         let mut cmd = Command::new(&chrome_path);
         cmd.arg("--remote-debugging-port=0"); // Let OS assign a random port
-        cmd.arg(format!("--user-data-dir={}", temp_dir.display()));
+        cmd.arg(format!("--user-data-dir={}", profile.dir().display()));
 
-        if headless {
-            cmd.arg("--headless");
+        if let Some(mode) = headless {
+            cmd.arg(format!("--headless={}", mode));
         }
 
         for arg in args {
             cmd.arg(arg);
         }
 
-        // Always capture stderr to read the assigned port
-        // Use a temporary file for stderr to avoid buffering issues with pipes
-        let stderr_file = temp_dir.join("chrome_stderr.log");
-        let stderr_handle = std::fs::File::create(&stderr_file)?;
-
+        // Kept in the profile to explain a failed start
+        let stderr_handle = std::fs::File::create(profile.dir().join(profile::STDERR_LOG))?;
         cmd.stdout(Stdio::null());
         cmd.stderr(Stdio::from(stderr_handle));
 
-        let process = cmd.spawn()?;
+        let child = cmd
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start browser {}: {}", chrome_path.display(), e))?;
+        let mut launched = Launched(Some(Chrome { child, profile }));
 
-        // Read the port from the stderr file
-        let port = Arc::new(Mutex::new(None::<u16>));
-        let port_clone = port.clone();
-        let stderr_path = stderr_file.clone();
-        let debug_flag = debug;
+        let ws_url = Self::wait_for_devtools(&mut launched).await?;
+        if debug {
+            eprintln!("Chrome: DevTools listening on {}", ws_url);
+        }
+
+        let mut browser = Self::attach(&ws_url).await?;
+        browser.process = Mutex::new(Some(launched));
+        Ok(browser)
+    }
 
-        // Spawn a thread to read the file and extract the port
-        std::thread::spawn(move || {
-            let file = match std::fs::File::open(&stderr_path) {
-                Ok(f) => f,
-                Err(_) => return,
+    /// Wait for Chrome to write `DevToolsActivePort` into its profile and return the
+    /// browser's WebSocket URL, failing early with its stderr if Chrome exits first
+    async fn wait_for_devtools(launched: &mut Chrome) -> Result<String> {
+        let file = launched.profile.dir().join("DevToolsActivePort");
+        let started = Instant::now();
+        loop {
+            if let Ok(content) = std::fs::read_to_string(&file)
+                && let Some(ws_url) = Self::parse_active_port(&content)
+            {
+                return Ok(ws_url);
+            }
+            let stderr = || match launched.profile.stderr_tail(20) {
+                tail if tail.is_empty() => String::new(),
+                tail => format!(":\n{}", tail),
             };
-            let _reader = BufReader::new(file);
-
-            // Poll the file for the port message
-            for _ in 0..100 {
-                // Try for 10 seconds
-                // We need to re-open or seek to read new content, but simple polling works for now
-                // Actually, let's just read the whole file each time since it's small
-                if let Ok(content) = std::fs::read_to_string(&stderr_path) {
-                    for line in content.lines() {
-                        if debug_flag && line.contains("DevTools listening on") {
-                            eprintln!("Chrome: {}", line);
-                        }
-
-                        if line.contains("DevTools listening on") {
-                            #[allow(clippy::collapsible_if)]
-                            if let Some(port_str) = line.split("127.0.0.1:").nth(1) {
-                                if let Some(port_num) = port_str.split('/').next() {
-                                    if let Ok(p) = port_num.parse::<u16>() {
-                                        if let Ok(mut guard) = port_clone.lock() {
-                                            *guard = Some(p);
-                                        }
-                                        return;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                std::thread::sleep(Duration::from_millis(100));
+            if let Some(status) = launched.child.try_wait()? {
+                return Err(anyhow!("Chrome exited with {} during startup{}", status, stderr()));
             }
-        });
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(anyhow!(
+                    "Chrome did not open its DevTools port within {:?}{}",
+                    STARTUP_TIMEOUT,
+                    stderr()
+                ));
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    }
 
-        // Wait for the port to be discovered (up to 10 seconds)
-        let discovered_port = tokio::task::spawn_blocking(move || {
-            for _ in 0..100 {
-                let port_val = port.lock().map_or(None, |guard| *guard);
+    /// WebSocket URL from the `DevToolsActivePort` contents: the port, then the browser path
+    fn parse_active_port(content: &str) -> Option<String> {
+        let mut lines = content.lines();
+        let port: u16 = lines.next()?.trim().parse().ok()?;
+        let path = lines.next()?.trim();
+        path.starts_with('/').then(|| format!("ws://127.0.0.1:{}{}", port, path))
+    }
 
-                if let Some(p) = port_val {
-                    return Ok(p);
-                }
-                std::thread::sleep(Duration::from_millis(100));
+    /// Attach to a Chrome that is already running, without managing its process
+    ///
+    /// `url` is either the HTTP DevTools address (`http://host:9222`), whose `/json/version`
+    /// names the browser's WebSocket, or that WebSocket URL itself (`ws://host:9222/devtools/
+    /// browser/...`). Tabs opened by this browser are closed again; other tabs are left alone.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow!("Invalid browser URL {:?}: {}", url, e))?;
+        let ws_url = match parsed.scheme() {
+            "ws" | "wss" => url.to_string(),
+            "http" | "https" => Self::get_ws_url(parsed.as_str().trim_end_matches('/'))
+                .await
+                .map_err(|e| anyhow!("Could not reach the browser at {}: {}", url, e))?,
+            scheme => {
+                return Err(anyhow!(
+                    "Unsupported browser URL scheme {:?} (expected http, https, ws or wss)",
+                    scheme
+                ));
             }
-            Err(anyhow!("Failed to discover Chrome debugging port"))
+        };
+        Self::attach(&ws_url).await
+    }
+
+    /// Connect to the browser-level WebSocket at `ws_url`
+    async fn attach(ws_url: &str) -> Result<Self> {
+        let endpoint = Self::endpoint(ws_url)?;
+        let connection = Arc::new(CdpConnection::connect(ws_url).await?);
+        Ok(Self {
+            process: Mutex::new(None),
+            endpoint,
+            connection,
+            idle: Mutex::new(Vec::new()),
+            page_permits: Semaphore::new(DEFAULT_PAGE_LIMIT),
         })
-        .await??;
-
-        // Wait for Chrome to start and expose the debugging port
-        // Retry get_ws_url with backoff instead of fixed sleep
-        let ws_url =
-            Self::get_ws_url_with_retry(discovered_port, 10, Duration::from_millis(500)).await?;
-
-        Ok(Self { process: Some(process), port: discovered_port, ws_url })
-    }
-
-    /// Get WebSocket debugger URL from Chrome with retry logic
-    async fn get_ws_url_with_retry(
-        port: u16,
-        max_retries: u32,
-        retry_delay: Duration,
-    ) -> Result<String> {
-        let mut last_error = None;
-
-        for attempt in 0..max_retries {
-            match Self::get_ws_url(port).await {
-                Ok(url) => return Ok(url),
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries - 1 {
-                        sleep(retry_delay).await;
-                    }
-                }
-            }
+    }
+
+    /// `scheme://host:port` of a WebSocket URL, with the scheme kept for `wss`
+    fn endpoint(ws_url: &str) -> Result<String> {
+        let url = reqwest::Url::parse(ws_url)
+            .map_err(|e| anyhow!("Invalid WebSocket URL {:?}: {}", ws_url, e))?;
+        let host = url.host_str().ok_or_else(|| anyhow!("No host in {:?}", ws_url))?;
+        Ok(match url.port() {
+            Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+            None => format!("{}://{}", url.scheme(), host),
+        })
+    }
+
+    /// Whether this browser was attached to with [`CdpBrowser::connect`] rather than launched
+    pub fn is_attached(&self) -> bool {
+        self.process.lock().is_ok_and(|process| process.is_none())
+    }
+
+    /// Limit how many pooled tabs can be in use at once
+    pub fn with_page_limit(mut self, limit: usize) -> Self {
+        self.page_permits = Semaphore::new(limit.max(1));
+        self
+    }
+
+    /// Whether the browser process is running and its DevTools connection is open
+    pub fn is_alive(&self) -> bool {
+        // An attached browser's process is not ours to watch, only its connection
+        let running = match self.process.lock() {
+            Ok(mut process) => match process.as_mut() {
+                Some(launched) => matches!(launched.child.try_wait(), Ok(None)),
+                None => true,
+            },
+            Err(_) => false,
+        };
+        running && self.connection.is_alive()
+    }
+
+    /// Why the browser stopped responding, or `None` while it is healthy
+    pub fn failure(&self) -> Option<String> {
+        if let Ok(mut process) = self.process.lock()
+            && let Some(launched) = process.as_mut()
+            && let Ok(Some(status)) = launched.child.try_wait()
+        {
+            return Some(format!("Chrome exited with {}", status));
         }
+        self.connection
+            .close_reason()
+            .map(|reason| format!("DevTools connection closed: {}", reason))
+    }
 
-        Err(last_error.unwrap_or_else(|| anyhow!("Failed to get WebSocket URL after retries")))
+    /// Borrow a tab from the pool, waiting while the page limit is reached
+    ///
+    /// Idle tabs are reused; a new one is opened when none is available. Idle tabs whose
+    /// connection has closed are discarded.
+    pub async fn page(&self) -> Result<PooledPage<'_>> {
+        let permit = self.page_permits.acquire().await?;
+        let idle = {
+            let mut idle = self.idle.lock().map_err(|_| anyhow!("Page pool poisoned"))?;
+            idle.retain(CdpPage::is_alive);
+            idle.pop()
+        };
+        let page = match idle {
+            Some(page) => page,
+            None => self.new_page().await?,
+        };
+        Ok(PooledPage::new(page, &self.idle, permit))
     }
 
-    /// Get WebSocket debugger URL from Chrome
-    async fn get_ws_url(port: u16) -> Result<String> {
+    /// Get WebSocket debugger URL from the DevTools HTTP endpoint at `base`
+    async fn get_ws_url(base: &str) -> Result<String> {
         let client = reqwest::Client::new();
-        let response: Value = client
-            .get(format!("http://127.0.0.1:{}/json/version", port))
-            .send()
-            .await?
-            .json()
-            .await?;
+        let response: Value =
+            client.get(format!("{}/json/version", base)).send().await?.json().await?;
 
         response["webSocketDebuggerUrl"]
             .as_str()
@@ -175,27 +244,224 @@ impl CdpBrowser {
             .ok_or_else(|| anyhow!("Could not find webSocketDebuggerUrl"))
     }
 
-    /// Create a new page and return its WebSocket URL
-    pub async fn new_page(&self) -> Result<String> {
-        let client = reqwest::Client::new();
-        let response: Value = client
-            .put(format!("http://127.0.0.1:{}/json/new", self.port))
-            .send()
-            .await?
-            .json()
+    /// Open a new tab and connect to it; the tab is closed when the page is closed or dropped
+    pub async fn new_page(&self) -> Result<CdpPage> {
+        let result = self
+            .connection
+            .send_command("Target.createTarget", json!({ "url": "about:blank" }))
             .await?;
-
-        response["webSocketDebuggerUrl"]
+        let target_id = result["targetId"]
             .as_str()
             .map(String::from)
-            .ok_or_else(|| anyhow!("Could not find webSocketDebuggerUrl for new page"))
+            .ok_or_else(|| anyhow!("Could not find targetId for new page"))?;
+
+        let ws_url = format!("{}/devtools/page/{}", self.endpoint, target_id);
+        match CdpPage::attach(&ws_url, target_id.clone(), self.connection.clone()).await {
+            Ok(page) => Ok(page),
+            Err(e) => {
+                let _ = self
+                    .connection
+                    .send_detached("Target.closeTarget", json!({ "targetId": target_id }));
+                Err(e)
+            }
+        }
     }
 }
 
-impl Drop for CdpBrowser {
+/// Chrome launched by [`CdpBrowser::launch`], killed and its profile deleted on drop
+struct Launched(Option<Chrome>);
+
+/// Process and profile of a launched Chrome
+struct Chrome {
+    child: Child,
+    profile: Profile,
+}
+
+impl Chrome {
+    /// Kill Chrome and wait for it to exit, so the profile can be deleted after it
+    fn shut_down(mut self) {
+        let _ = self.child.kill();
+        let started = Instant::now();
+        while matches!(self.child.try_wait(), Ok(None)) && started.elapsed() < EXIT_TIMEOUT {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Deref for Launched {
+    type Target = Chrome;
+
+    fn deref(&self) -> &Chrome {
+        self.0.as_ref().expect("Chrome is only taken on drop")
+    }
+}
+
+impl DerefMut for Launched {
+    fn deref_mut(&mut self) -> &mut Chrome {
+        self.0.as_mut().expect("Chrome is only taken on drop")
+    }
+}
+
+impl Drop for Launched {
+    /// Shut Chrome down without stalling the async runtime the browser is dropped on
     fn drop(&mut self) {
-        if let Some(mut process) = self.process.take() {
-            let _ = process.kill();
+        let Some(chrome) = self.0.take() else {
+            return;
+        };
+        match Handle::try_current().map(|runtime| (runtime.runtime_flavor(), runtime)) {
+            // Other tasks move to another worker while this one waits
+            Ok((RuntimeFlavor::MultiThread, _)) => {
+                tokio::task::block_in_place(|| chrome.shut_down())
+            }
+            Ok((_, runtime)) => drop(runtime.spawn_blocking(|| chrome.shut_down())),
+            Err(_) => chrome.shut_down(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cdp::page::tests::fake_browser;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Browser opening tabs named `tab`
+    fn tabs(method: &str) -> (Value, Vec<Value>) {
+        match method {
+            "Target.createTarget" => (json!({ "targetId": "tab" }), Vec::new()),
+            _ => (json!({}), Vec::new()),
+        }
+    }
+
+    /// HTTP server answering `/json/version` with `ws_url` as the browser's WebSocket
+    async fn devtools_http(ws_url: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let n = stream.read(&mut request).await.unwrap();
+                assert!(String::from_utf8_lossy(&request[..n]).starts_with("GET /json/version "));
+                let body = json!({ "webSocketDebuggerUrl": ws_url }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_connect_to_running_browser() {
+        let (ws_url, mut calls) = fake_browser(tabs).await;
+        let http_url = devtools_http(ws_url.clone()).await;
+
+        for url in [http_url.clone(), format!("{}/", http_url), ws_url] {
+            let browser = CdpBrowser::connect(&url).await.unwrap();
+            assert!(browser.is_attached());
+            assert!(browser.is_alive() && browser.failure().is_none());
+
+            let page = browser.page().await.unwrap();
+            assert_eq!(page.target_id(), "tab");
+            page.release();
         }
+        let mut methods = Vec::new();
+        while let Ok((method, _)) = calls.try_recv() {
+            methods.push(method);
+        }
+        assert!(methods.contains(&"Target.createTarget".to_string()), "{:?}", methods);
+    }
+
+    #[tokio::test]
+    async fn test_connect_rejects_unreachable_or_invalid_urls() {
+        assert!(CdpBrowser::connect("chromium:9222").await.is_err());
+        assert!(CdpBrowser::connect("ftp://127.0.0.1:9222").await.is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let error = CdpBrowser::connect(&url).await.err().unwrap();
+        assert!(error.to_string().contains("Could not reach the browser"), "{}", error);
+    }
+
+    /// Shell script standing in for Chrome: runs `body` with `$dir` set to the profile
+    #[cfg(unix)]
+    fn fake_chrome(dir: &std::path::Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("chrome");
+        let script = format!(
+            "#!/bin/sh\nfor arg in \"$@\"; do case \"$arg\" in --user-data-dir=*) \
+             dir=\"${{arg#--user-data-dir=}}\";; --port=*) port=\"${{arg#--port=}}\";; \
+             esac; done\n{}\n",
+            body
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_launch_reads_active_port_and_cleans_up() {
+        let (ws_url, _calls) = fake_browser(tabs).await;
+        let port = ws_url.rsplit(':').next().unwrap().to_string();
+        let bin = tempfile::tempdir().unwrap();
+        let chrome = fake_chrome(
+            bin.path(),
+            "printf '%s\\n/devtools/browser/test\\n' \"$port\" > \"$dir/DevToolsActivePort\"\n\
+             exec sleep 30",
+        );
+
+        let port_arg = format!("--port={}", port);
+        let browser =
+            CdpBrowser::launch(Some(chrome), vec![&port_arg], Some(HeadlessMode::New), false)
+                .await
+                .unwrap();
+        assert!(!browser.is_attached() && browser.is_alive());
+        let profile = browser.process.lock().unwrap().as_ref().unwrap().profile.dir().to_owned();
+        assert!(profile.join("DevToolsActivePort").exists());
+
+        drop(browser);
+        assert!(!profile.exists(), "profile {} left behind", profile.display());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_launch_reports_early_exit_with_stderr() {
+        let bin = tempfile::tempdir().unwrap();
+        let chrome = fake_chrome(bin.path(), "echo 'Missing X server or $DISPLAY' >&2\nexit 1");
+
+        let error = CdpBrowser::launch(Some(chrome), vec![], Some(HeadlessMode::New), false)
+            .await
+            .err()
+            .unwrap();
+        let message = error.to_string();
+        assert!(message.contains("exited with exit status: 1"), "{}", message);
+        assert!(message.contains("Missing X server"), "{}", message);
+    }
+
+    #[test]
+    fn test_parse_active_port() {
+        let parse = CdpBrowser::parse_active_port;
+        assert_eq!(
+            parse("9222\n/devtools/browser/abc\n").as_deref(),
+            Some("ws://127.0.0.1:9222/devtools/browser/abc")
+        );
+        // Chrome may not have finished writing the file
+        assert_eq!(parse("9222\n"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn test_endpoint_of_websocket_url() {
+        let endpoint = |url| CdpBrowser::endpoint(url).unwrap();
+        assert_eq!(endpoint("ws://127.0.0.1:9222/devtools/browser/abc"), "ws://127.0.0.1:9222");
+        assert_eq!(endpoint("wss://chrome.example/devtools/browser/abc"), "wss://chrome.example");
+        assert_eq!(endpoint("ws://[::1]:9222/devtools/browser/abc"), "ws://[::1]:9222");
     }
 }
@@ -2,16 +2,40 @@ use anyhow::{Result, anyhow};
 use futures::{SinkExt, StreamExt};
 use serde_json::{Value, json};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc, oneshot};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast, mpsc, oneshot};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 type Responder = oneshot::Sender<Result<Value>>;
+type Pending = Arc<Mutex<HashMap<u32, Responder>>>;
+
+/// Events buffered per subscriber before the slowest one starts missing events
+const EVENT_BUFFER: usize = 256;
+
+/// How long `send_command` waits for a response
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Event pushed by the browser, e.g. `Page.lifecycleEvent`
+#[derive(Debug, Clone)]
+pub struct CdpEvent {
+    pub method: String,
+    pub params: Value,
+}
 
 /// CDP connection managing WebSocket communication
+///
+/// When the socket closes, every pending and later command fails with the reason instead of
+/// waiting for a response that will never come.
 pub struct CdpConnection {
     command_tx: mpsc::UnboundedSender<(u32, String, Value, Responder)>,
-    next_id: Arc<Mutex<u32>>,
+    next_id: AtomicU32,
+    events: broadcast::Sender<CdpEvent>,
+    pending: Pending,
+    /// Why the socket closed, set once the read loop has ended
+    closed: Arc<OnceLock<String>>,
+    command_timeout: Duration,
 }
 
 impl CdpConnection {
@@ -22,10 +46,12 @@ impl CdpConnection {
 
         let (command_tx, mut command_rx) =
             mpsc::unbounded_channel::<(u32, String, Value, Responder)>();
-        let pending: Arc<Mutex<HashMap<u32, Responder>>> = Arc::new(Mutex::new(HashMap::new()));
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let closed: Arc<OnceLock<String>> = Arc::new(OnceLock::new());
 
         // Task for sending commands
         let pending_clone = pending.clone();
+        let closed_clone = closed.clone();
         tokio::spawn(async move {
             while let Some((id, method, params, responder)) = command_rx.recv().await {
                 let msg = json!({
@@ -34,25 +60,45 @@ impl CdpConnection {
                     "params": params
                 });
 
-                pending_clone.lock().await.insert(id, responder);
+                // The read loop may have failed the pending commands already; checked under the
+                // lock it drains them with, so the command is failed either here or there
+                {
+                    let mut pending = pending_clone.lock().await;
+                    if let Some(reason) = closed_clone.get() {
+                        let _ = responder.send(Err(Self::closed_error(reason)));
+                        continue;
+                    }
+                    pending.insert(id, responder);
+                }
 
                 if let Err(e) = write.send(Message::Text(msg.to_string().into())).await {
-                    eprintln!("Failed to send CDP command: {}", e);
-                    break;
+                    let reason = format!("failed to send {}: {}", method, e);
+                    if let Some(responder) = pending_clone.lock().await.remove(&id) {
+                        let _ = responder.send(Err(Self::closed_error(&reason)));
+                    }
                 }
             }
         });
 
-        // Task for receiving responses
+        // Task for receiving responses and events
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let events_clone = events.clone();
+        let pending_clone = pending.clone();
+        let closed_clone = closed.clone();
         tokio::spawn(async move {
-            while let Some(msg) = read.next().await {
+            let reason = loop {
+                let msg = match read.next().await {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => break format!("WebSocket error: {}", e),
+                    None => break "socket closed by the browser".to_string(),
+                };
                 match msg {
-                    Ok(Message::Text(text)) => {
+                    Message::Text(text) => {
                         if let Ok(v) = serde_json::from_str::<Value>(&text) {
                             // Handle response
                             if let Some(id) = v["id"].as_u64() {
                                 let id = id as u32;
-                                let responder = pending.lock().await.remove(&id);
+                                let responder = pending_clone.lock().await.remove(&id);
                                 if let Some(responder) = responder {
                                     if let Some(error) = v.get("error") {
                                         let _ = responder.send(Err(anyhow!(
@@ -63,37 +109,158 @@ impl CdpConnection {
                                         let _ = responder.send(Ok(result.clone()));
                                     }
                                 }
+                            } else if let Some(method) = v["method"].as_str() {
+                                // Nobody listening is fine, events are only kept for subscribers
+                                let _ = events_clone.send(CdpEvent {
+                                    method: method.to_string(),
+                                    params: v["params"].clone(),
+                                });
                             }
-                            // Ignore events for now (we'll handle them separately if needed)
                         }
                     }
-                    Ok(Message::Close(_)) => break,
-                    Err(e) => {
-                        eprintln!("WebSocket error: {}", e);
-                        break;
+                    Message::Close(Some(frame)) if !frame.reason.is_empty() => {
+                        break format!("closed by the browser ({}): {}", frame.code, frame.reason);
                     }
+                    Message::Close(Some(frame)) => {
+                        break format!("closed by the browser ({})", frame.code);
+                    }
+                    Message::Close(None) => break "closed by the browser".to_string(),
                     _ => {}
                 }
+            };
+
+            // Set the reason before draining so the sender task fails late commands itself
+            let reason = closed_clone.get_or_init(|| reason);
+            for (_, responder) in pending_clone.lock().await.drain() {
+                let _ = responder.send(Err(Self::closed_error(reason)));
             }
         });
 
-        Ok(Self { command_tx, next_id: Arc::new(Mutex::new(1)) })
+        Ok(Self {
+            command_tx,
+            next_id: AtomicU32::new(1),
+            events,
+            pending,
+            closed,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+        })
     }
 
-    /// Send a CDP command and wait for response
+    /// Whether the socket is still open
+    pub fn is_alive(&self) -> bool {
+        self.closed.get().is_none() && !self.command_tx.is_closed()
+    }
+
+    /// Why the socket closed, or `None` while it is open
+    pub fn close_reason(&self) -> Option<&str> {
+        self.closed.get().map(String::as_str)
+    }
+
+    /// Receive every event arriving from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<CdpEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send a CDP command and wait for response, for at most the command timeout
     pub async fn send_command(&self, method: &str, params: Value) -> Result<Value> {
-        let id = {
-            let mut next_id = self.next_id.lock().await;
-            let id = *next_id;
-            *next_id += 1;
-            id
-        };
+        self.send_command_with_timeout(method, params, self.command_timeout).await
+    }
+
+    /// Send a CDP command and wait for response for at most `limit`
+    pub async fn send_command_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        limit: Duration,
+    ) -> Result<Value> {
+        let (id, rx) = self.enqueue(method, params)?;
+        match tokio::time::timeout(limit, rx).await {
+            Ok(response) => response.map_err(|_| self.channel_error())?,
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                Err(anyhow!("CDP command {} timed out after {:?}", method, limit))
+            }
+        }
+    }
 
+    /// Send a CDP command without waiting for the response, e.g. from `Drop`
+    pub fn send_detached(&self, method: &str, params: Value) -> Result<()> {
+        self.enqueue(method, params).map(drop)
+    }
+
+    fn enqueue(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<(u32, oneshot::Receiver<Result<Value>>)> {
+        if let Some(reason) = self.close_reason() {
+            return Err(Self::closed_error(reason));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         let (tx, rx) = oneshot::channel();
         self.command_tx
             .send((id, method.to_string(), params, tx))
-            .map_err(|_| anyhow!("Failed to send command"))?;
+            .map_err(|_| self.channel_error())?;
+        Ok((id, rx))
+    }
+
+    fn channel_error(&self) -> anyhow::Error {
+        Self::closed_error(self.close_reason().unwrap_or("connection tasks stopped"))
+    }
+
+    fn closed_error(reason: &str) -> anyhow::Error {
+        anyhow!("CDP connection closed: {}", reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+    /// WebSocket server that never answers, or closes with `close_reason` on the first command
+    async fn fake_browser(close_reason: Option<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {
+                if let Some(reason) = close_reason {
+                    let frame = CloseFrame { code: CloseCode::Error, reason: reason.into() };
+                    let _ = ws.close(Some(frame)).await;
+                }
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_command_times_out() {
+        let url = fake_browser(None).await;
+        let mut connection = CdpConnection::connect(&url).await.unwrap();
+        connection.command_timeout = Duration::from_millis(100);
+
+        let error = connection.send_command("Page.enable", json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("Page.enable timed out"), "{}", error);
+        assert!(connection.pending.lock().await.is_empty());
+        assert!(connection.is_alive());
+    }
+
+    #[tokio::test]
+    async fn test_close_fails_pending_commands_with_reason() {
+        let url = fake_browser(Some("renderer crashed")).await;
+        let connection = CdpConnection::connect(&url).await.unwrap();
+
+        let error = connection.send_command("Page.enable", json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("renderer crashed"), "{}", error);
+        assert!(!connection.is_alive());
+        assert!(connection.close_reason().unwrap().contains("renderer crashed"));
 
-        rx.await.map_err(|_| anyhow!("Response channel closed"))?
+        // Later commands fail immediately instead of waiting for the timeout
+        let error = connection.send_command("Runtime.enable", json!({})).await.unwrap_err();
+        assert!(error.to_string().starts_with("CDP connection closed"), "{}", error);
     }
 }
@@ -0,0 +1,221 @@
+//! Discovery of installed Chrome-family browsers
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variables naming the browser executable, checked in order
+const ENV_VARS: &[&str] = &["CHROME", "CHROME_PATH"];
+
+/// Executable names looked up in `PATH`, preferred first
+const NAMES: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+    "brave-browser",
+    "brave",
+    "microsoft-edge",
+    "microsoft-edge-stable",
+    "msedge",
+];
+
+/// Browser executable found on this system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Candidate {
+    pub path: PathBuf,
+    /// Where the executable was found: an environment variable, `PATH` or `well-known`
+    pub source: String,
+}
+
+/// Every browser executable found, in the order [`find`] prefers them
+///
+/// `CHROME` and `CHROME_PATH` come first, then the names above in `PATH`, then the usual
+/// install locations of Chrome, Chromium, Brave and Edge including snap and flatpak exports.
+/// The same executable reached through several paths is listed once.
+pub fn candidates() -> Vec<Candidate> {
+    search(|var| std::env::var_os(var))
+}
+
+/// The browser executable to launch when none is configured
+///
+/// An environment variable pointing at something that is not an executable is an error rather
+/// than silently falling back to another browser.
+pub fn find() -> Result<Candidate> {
+    for var in ENV_VARS {
+        if let Some(path) = std::env::var_os(var).filter(|path| !path.is_empty())
+            && !is_executable(Path::new(&path))
+        {
+            return Err(anyhow!("{} is set to {:?}, which is not an executable", var, path));
+        }
+    }
+    candidates().into_iter().next().ok_or_else(|| {
+        anyhow!(
+            "No Chrome, Chromium, Brave or Edge found in PATH or the usual install locations; \
+             install one, set CHROME_PATH or run `arxiv-cli config set browser_path <path>`"
+        )
+    })
+}
+
+/// Candidates found with the environment given by `env`
+fn search(env: impl Fn(&str) -> Option<OsString>) -> Vec<Candidate> {
+    let mut found = Vec::new();
+    for var in ENV_VARS {
+        if let Some(path) = env(var).filter(|path| !path.is_empty()) {
+            found.push(Candidate { path: PathBuf::from(path), source: var.to_string() });
+        }
+    }
+    if let Some(dirs) = env("PATH") {
+        for name in NAMES {
+            let file = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+            for dir in std::env::split_paths(&dirs) {
+                found.push(Candidate { path: dir.join(&file), source: "PATH".to_string() });
+            }
+        }
+    }
+    for path in well_known(&env) {
+        found.push(Candidate { path, source: "well-known".to_string() });
+    }
+
+    let mut seen = Vec::new();
+    found.retain(|candidate| {
+        if !is_executable(&candidate.path) {
+            return false;
+        }
+        let target = candidate.path.canonicalize().unwrap_or_else(|_| candidate.path.clone());
+        if seen.contains(&target) {
+            return false;
+        }
+        seen.push(target);
+        true
+    });
+    found
+}
+
+/// Usual install locations on this platform
+fn well_known(env: &impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let dir = |var: &str| env(var).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    let mut paths = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        const FLATPAKS: &[&str] = &[
+            "com.google.Chrome",
+            "org.chromium.Chromium",
+            "com.brave.Browser",
+            "com.microsoft.Edge",
+        ];
+        paths.extend(
+            [
+                "/usr/bin/google-chrome",
+                "/usr/bin/google-chrome-stable",
+                "/usr/bin/chromium",
+                "/usr/bin/chromium-browser",
+                "/snap/bin/chromium",
+                "/opt/google/chrome/chrome",
+                "/opt/brave.com/brave/brave",
+                "/opt/microsoft/msedge/msedge",
+            ]
+            .map(PathBuf::from),
+        );
+        let exports = std::iter::once(PathBuf::from("/var/lib/flatpak/exports/bin"))
+            .chain(dir("HOME").map(|home| home.join(".local/share/flatpak/exports/bin")));
+        for exports in exports {
+            paths.extend(FLATPAKS.iter().map(|app| exports.join(app)));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        const APPS: &[&str] = &[
+            "Google Chrome.app/Contents/MacOS/Google Chrome",
+            "Chromium.app/Contents/MacOS/Chromium",
+            "Brave Browser.app/Contents/MacOS/Brave Browser",
+            "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ];
+        let roots = std::iter::once(PathBuf::from("/Applications"))
+            .chain(dir("HOME").map(|home| home.join("Applications")));
+        for root in roots {
+            paths.extend(APPS.iter().map(|app| root.join(app)));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        const INSTALLS: &[&str] = &[
+            "Google\\Chrome\\Application\\chrome.exe",
+            "Chromium\\Application\\chrome.exe",
+            "BraveSoftware\\Brave-Browser\\Application\\brave.exe",
+            "Microsoft\\Edge\\Application\\msedge.exe",
+        ];
+        for root in
+            ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"].into_iter().filter_map(dir)
+        {
+            paths.extend(INSTALLS.iter().map(|install| root.join(install)));
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let _ = dir;
+
+    paths
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata().is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn touch(dir: &Path, name: &str, mode: u32) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_search_order_and_deduplication() {
+        let bin = tempfile::tempdir().unwrap();
+        let opt = tempfile::tempdir().unwrap();
+        let chromium = touch(bin.path(), "chromium", 0o755);
+        let brave = touch(opt.path(), "brave", 0o755);
+        touch(bin.path(), "google-chrome", 0o644);
+        std::os::unix::fs::symlink(&chromium, bin.path().join("chromium-browser")).unwrap();
+
+        let path = std::env::join_paths([bin.path(), opt.path()]).unwrap();
+        let env = HashMap::from([
+            ("PATH", path),
+            ("CHROME_PATH", brave.clone().into_os_string()),
+            ("CHROME", OsString::new()),
+        ]);
+        let found = search(|var| env.get(var).cloned());
+
+        // Non-executables are skipped, the symlink and the PATH hit of CHROME_PATH are dropped
+        let expected = [(brave, "CHROME_PATH"), (chromium, "PATH")];
+        let found = found.iter().take(2).map(|c| (c.path.clone(), c.source.as_str()));
+        assert_eq!(found.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_search_without_environment_only_checks_well_known_locations() {
+        for candidate in search(|_| None) {
+            assert_eq!(candidate.source, "well-known");
+            assert!(is_executable(&candidate.path));
+        }
+    }
+}
@@ -1,6 +1,11 @@
 mod browser;
 mod connection;
+pub mod discovery;
 mod page;
+mod pool;
+mod profile;
 
 pub use browser::CdpBrowser;
-pub use page::CdpPage;
+pub use connection::CdpEvent;
+pub use page::{CdpPage, WaitUntil};
+pub use pool::PooledPage;
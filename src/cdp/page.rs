@@ -1,65 +1,196 @@
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::sleep;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::timeout;
 
-use super::connection::CdpConnection;
+use super::connection::{CdpConnection, CdpEvent};
+
+/// How long `goto` waits for the page to load
+const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time allowed on top of a script's own timeout for the evaluation round trip
+const EVALUATE_SLACK: Duration = Duration::from_secs(5);
+
+/// Point of the page load `goto_until` waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitUntil {
+    /// The HTML is parsed (`DOMContentLoaded`); images and stylesheets may still be loading
+    DomContentLoaded,
+    /// The `load` event fired
+    #[default]
+    Load,
+    /// No network connections for 500 ms after loading
+    NetworkIdle,
+}
+
+impl WaitUntil {
+    /// Name of the matching `Page.lifecycleEvent`
+    fn lifecycle_name(self) -> &'static str {
+        match self {
+            WaitUntil::DomContentLoaded => "DOMContentLoaded",
+            WaitUntil::Load => "load",
+            WaitUntil::NetworkIdle => "networkIdle",
+        }
+    }
+}
+
+impl fmt::Display for WaitUntil {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.lifecycle_name())
+    }
+}
 
 /// CDP Page for browser automation
+///
+/// Owns its browser tab: the tab is closed with `Target.closeTarget` by [`CdpPage::close`] or,
+/// failing that, when the page is dropped.
 pub struct CdpPage {
     connection: CdpConnection,
+    target_id: String,
+    /// Browser-level connection the tab was created on, used to close it
+    browser: Arc<CdpConnection>,
+    closed: bool,
 }
 
 impl CdpPage {
-    /// Create a new page with the given connection
-    pub async fn new(ws_url: &str) -> Result<Self> {
+    /// Attach to the tab `target_id` through its WebSocket URL
+    pub(super) async fn attach(
+        ws_url: &str,
+        target_id: String,
+        browser: Arc<CdpConnection>,
+    ) -> Result<Self> {
         let connection = CdpConnection::connect(ws_url).await?;
+        let page = Self { connection, target_id, browser, closed: false };
 
         // Enable necessary domains
-        connection.send_command("Page.enable", json!({})).await?;
-        connection.send_command("Runtime.enable", json!({})).await?;
+        page.connection.send_command("Page.enable", json!({})).await?;
+        page.connection.send_command("Runtime.enable", json!({})).await?;
+        page.connection
+            .send_command("Page.setLifecycleEventsEnabled", json!({ "enabled": true }))
+            .await?;
 
-        Ok(Self { connection })
+        Ok(page)
     }
 
-    /// Navigate to a URL
-    pub async fn goto(&self, url: &str) -> Result<()> {
-        self.connection.send_command("Page.navigate", json!({ "url": url })).await?;
+    /// Identifier of the tab in the browser
+    pub fn target_id(&self) -> &str {
+        &self.target_id
+    }
 
-        Ok(())
+    /// Whether the tab's connection is still open
+    pub fn is_alive(&self) -> bool {
+        self.connection.is_alive()
     }
 
-    /// Wait for an element to appear on the page
-    pub async fn wait_for_element(&self, selector: &str, timeout_secs: u64) -> Result<bool> {
-        let start = std::time::Instant::now();
+    /// Receive the events of this tab, e.g. `Page.lifecycleEvent`, from now on
+    pub fn events(&self) -> broadcast::Receiver<CdpEvent> {
+        self.connection.subscribe()
+    }
 
-        while start.elapsed().as_secs() < timeout_secs {
-            let script = format!("!!document.querySelector(\"{}\")", selector.replace('"', "\\\""));
+    /// Navigate to a URL and wait for the `load` event
+    pub async fn goto(&self, url: &str) -> Result<()> {
+        self.goto_until(url, WaitUntil::Load, NAVIGATION_TIMEOUT).await
+    }
 
-            let result = self.evaluate(&script).await?;
-            if result.as_bool().unwrap_or(false) {
-                return Ok(true);
+    /// Navigate to a URL and wait until the new document reaches `wait_until`
+    ///
+    /// Lifecycle events are matched on the navigation's loader, so events still arriving from
+    /// the previous document of a reused tab are ignored.
+    pub async fn goto_until(
+        &self,
+        url: &str,
+        wait_until: WaitUntil,
+        limit: Duration,
+    ) -> Result<()> {
+        // Subscribe before navigating so no event is missed
+        let mut events = self.events();
+        let navigation = async {
+            let result =
+                self.connection.send_command("Page.navigate", json!({ "url": url })).await?;
+            if let Some(error) = result["errorText"].as_str() {
+                return Err(anyhow!("Failed to navigate to {}: {}", url, error));
+            }
+            // Same-document navigations (e.g. to an anchor) have no loader and nothing to wait for
+            let Some(loader_id) = result["loaderId"].as_str() else {
+                return Ok(());
+            };
+            loop {
+                match events.recv().await {
+                    Ok(event) if Self::is_lifecycle(&event, loader_id, wait_until) => return Ok(()),
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        return Err(anyhow!("Connection closed while loading {}", url));
+                    }
+                }
             }
+        };
+        timeout(limit, navigation).await.map_err(|_| {
+            anyhow!("Timed out after {:?} waiting for {} on {}", limit, wait_until, url)
+        })?
+    }
 
-            sleep(Duration::from_millis(500)).await;
-        }
+    /// Whether `event` marks `wait_until` for the document of `loader_id`
+    fn is_lifecycle(event: &CdpEvent, loader_id: &str, wait_until: WaitUntil) -> bool {
+        event.method == "Page.lifecycleEvent"
+            && event.params["loaderId"] == loader_id
+            && event.params["name"] == wait_until.lifecycle_name()
+    }
 
-        Ok(false)
+    /// Close the tab and wait for the browser to confirm
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+        self.browser
+            .send_command("Target.closeTarget", json!({ "targetId": self.target_id }))
+            .await?;
+        Ok(())
+    }
+
+    /// Wait for an element to appear on the page
+    ///
+    /// A `MutationObserver` in the page resolves as soon as the element is inserted.
+    pub async fn wait_for_element(&self, selector: &str, timeout_secs: u64) -> Result<bool> {
+        let script = format!(
+            r#"new Promise(resolve => {{
+                const selector = {selector};
+                if (document.querySelector(selector)) return resolve(true);
+                const observer = new MutationObserver(() => {{
+                    if (document.querySelector(selector)) {{
+                        observer.disconnect();
+                        clearTimeout(timer);
+                        resolve(true);
+                    }}
+                }});
+                observer.observe(document, {{ childList: true, subtree: true }});
+                const timer = setTimeout(() => {{ observer.disconnect(); resolve(false); }}, {ms});
+            }})"#,
+            selector = serde_json::to_string(selector)?,
+            ms = timeout_secs * 1000,
+        );
+        let limit = Duration::from_secs(timeout_secs) + EVALUATE_SLACK;
+        Ok(self.evaluate_within(&script, Some(limit)).await?.as_bool().unwrap_or(false))
     }
 
     /// Evaluate JavaScript and return the result
     pub async fn evaluate(&self, script: &str) -> Result<Value> {
-        let result = self
-            .connection
-            .send_command(
-                "Runtime.evaluate",
-                json!({
-                    "expression": script,
-                    "returnByValue": true,
-                    "awaitPromise": true
-                }),
-            )
-            .await?;
+        self.evaluate_within(script, None).await
+    }
+
+    /// Evaluate JavaScript, waiting `limit` instead of the command timeout when given
+    async fn evaluate_within(&self, script: &str, limit: Option<Duration>) -> Result<Value> {
+        let params = json!({
+            "expression": script,
+            "returnByValue": true,
+            "awaitPromise": true
+        });
+        let result = match limit {
+            Some(limit) => {
+                self.connection.send_command_with_timeout("Runtime.evaluate", params, limit).await?
+            }
+            None => self.connection.send_command("Runtime.evaluate", params).await?,
+        };
 
         if let Some(exception) = result.get("exceptionDetails") {
             return Err(anyhow!("JavaScript error: {:?}", exception));
@@ -68,3 +199,153 @@ impl CdpPage {
         Ok(result["result"]["value"].clone())
     }
 }
+
+impl Drop for CdpPage {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = self
+                .browser
+                .send_detached("Target.closeTarget", json!({ "targetId": self.target_id }));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Reply and follow-up events of the fake browser for a command
+    pub(in crate::cdp) type Script = fn(&str) -> (Value, Vec<Value>);
+
+    /// Browser answering every command with an empty result
+    fn quiet(_method: &str) -> (Value, Vec<Value>) {
+        (json!({}), Vec::new())
+    }
+
+    /// Browser whose navigations report lifecycle events of a stale and of the new document
+    fn loading(method: &str) -> (Value, Vec<Value>) {
+        let lifecycle = |loader: &str, name: &str| {
+            json!({ "method": "Page.lifecycleEvent",
+                    "params": { "frameId": "F", "loaderId": loader, "name": name } })
+        };
+        match method {
+            "Page.navigate" => (
+                json!({ "frameId": "F", "loaderId": "NEW" }),
+                vec![
+                    lifecycle("OLD", "load"),
+                    lifecycle("NEW", "init"),
+                    lifecycle("NEW", "DOMContentLoaded"),
+                    lifecycle("NEW", "load"),
+                ],
+            ),
+            _ => quiet(method),
+        }
+    }
+
+    /// WebSocket server replying to commands as `script` says and reporting the
+    /// `(method, params)` it received on any of its connections
+    pub(in crate::cdp) async fn fake_browser(
+        script: Script,
+    ) -> (String, mpsc::UnboundedReceiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (calls_tx, calls_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let calls_tx = calls_tx.clone();
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let command: Value = serde_json::from_str(&text).unwrap();
+                        let method = command["method"].as_str().unwrap().to_string();
+                        let (result, events) = script(&method);
+                        let _ = calls_tx.send((method, command["params"].clone()));
+                        let reply = json!({ "id": command["id"], "result": result });
+                        ws.send(Message::Text(reply.to_string().into())).await.unwrap();
+                        for event in events {
+                            ws.send(Message::Text(event.to_string().into())).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        (url, calls_rx)
+    }
+
+    async fn attached(script: Script) -> (CdpPage, mpsc::UnboundedReceiver<(String, Value)>) {
+        let (url, calls) = fake_browser(script).await;
+        let browser = Arc::new(CdpConnection::connect(&url).await.unwrap());
+        (CdpPage::attach(&url, "tab".into(), browser).await.unwrap(), calls)
+    }
+
+    /// Wait for the next `Target.closeTarget` call and return its target
+    async fn next_close(calls: &mut mpsc::UnboundedReceiver<(String, Value)>) -> String {
+        let wait = async {
+            while let Some((method, params)) = calls.recv().await {
+                if method == "Target.closeTarget" {
+                    return params["targetId"].as_str().unwrap().to_string();
+                }
+            }
+            panic!("fake browser stopped");
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait).await.expect("tab was not closed")
+    }
+
+    #[tokio::test]
+    async fn test_tab_closed_on_close_and_drop() {
+        let (url, mut calls) = fake_browser(quiet).await;
+        let browser = Arc::new(CdpConnection::connect(&url).await.unwrap());
+
+        let page = CdpPage::attach(&url, "tab-1".into(), browser.clone()).await.unwrap();
+        assert_eq!(page.target_id(), "tab-1");
+        page.close().await.unwrap();
+        assert_eq!(next_close(&mut calls).await, "tab-1");
+
+        let page = CdpPage::attach(&url, "tab-2".into(), browser.clone()).await.unwrap();
+        drop(page);
+        assert_eq!(next_close(&mut calls).await, "tab-2");
+
+        // `close` disarms the drop guard, so each tab is closed exactly once
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        while let Ok((method, params)) = calls.try_recv() {
+            assert_ne!(method, "Target.closeTarget", "closed twice: {}", params);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_goto_waits_for_lifecycle_event_of_new_document() {
+        let (page, mut calls) = attached(loading).await;
+        let mut events = page.events();
+
+        for wait_until in [WaitUntil::DomContentLoaded, WaitUntil::Load] {
+            let limit = Duration::from_secs(5);
+            page.goto_until("https://arxiv.org/", wait_until, limit).await.unwrap();
+        }
+        let mut methods = Vec::new();
+        while let Ok((method, _)) = calls.try_recv() {
+            methods.push(method);
+        }
+        assert!(methods.contains(&"Page.setLifecycleEventsEnabled".to_string()));
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.method, "Page.lifecycleEvent");
+        assert_eq!(event.params["loaderId"], "OLD");
+    }
+
+    #[tokio::test]
+    async fn test_goto_times_out() {
+        let (page, _calls) = attached(loading).await;
+        let limit = Duration::from_millis(200);
+        let error =
+            page.goto_until("https://arxiv.org/", WaitUntil::NetworkIdle, limit).await.unwrap_err();
+        assert!(error.to_string().contains("networkIdle"), "{}", error);
+
+        // Without a loader the navigation stayed within the document, there is nothing to await
+        let (page, _calls) = attached(quiet).await;
+        assert!(page.goto_until("https://arxiv.org/", WaitUntil::Load, limit).await.is_ok());
+    }
+}
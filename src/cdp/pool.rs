@@ -0,0 +1,40 @@
+use std::ops::Deref;
+use std::sync::Mutex;
+use tokio::sync::SemaphorePermit;
+
+use super::page::CdpPage;
+
+/// Tab borrowed from a [`CdpBrowser`](super::CdpBrowser) pool
+///
+/// Call [`PooledPage::release`] after a successful request to keep the tab for reuse; a tab
+/// dropped without being released may be in an unknown state and is closed instead.
+pub struct PooledPage<'a> {
+    page: Option<CdpPage>,
+    idle: &'a Mutex<Vec<CdpPage>>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<'a> PooledPage<'a> {
+    pub(super) fn new(
+        page: CdpPage,
+        idle: &'a Mutex<Vec<CdpPage>>,
+        permit: SemaphorePermit<'a>,
+    ) -> Self {
+        Self { page: Some(page), idle, _permit: permit }
+    }
+
+    /// Hand the tab back to the pool for the next request
+    pub fn release(mut self) {
+        if let (Some(page), Ok(mut idle)) = (self.page.take(), self.idle.lock()) {
+            idle.push(page);
+        }
+    }
+}
+
+impl Deref for PooledPage<'_> {
+    type Target = CdpPage;
+
+    fn deref(&self) -> &CdpPage {
+        self.page.as_ref().expect("page is only taken on release")
+    }
+}
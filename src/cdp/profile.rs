@@ -0,0 +1,159 @@
+//! Throwaway Chrome profiles in the temp directory
+
+use anyhow::{Result, anyhow};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Prefix of the profile directories created in the temp directory
+const PREFIX: &str = "chrome-";
+
+/// File held locked for as long as the profile is in use
+const LOCK_FILE: &str = "arxiv-cli.lock";
+
+/// Chrome's stderr, kept in the profile to report why a launch failed
+pub(super) const STDERR_LOG: &str = "chrome_stderr.log";
+
+/// Profiles of earlier versions, which had no lock, count as stale after this long
+const UNLOCKED_STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// User data directory of a launched browser, deleted on drop
+pub(super) struct Profile {
+    dir: PathBuf,
+    /// Locked while this profile exists so `sweep` leaves it alone
+    _lock: File,
+}
+
+impl Profile {
+    /// Create a new profile directory in the temp directory
+    pub(super) fn create() -> Result<Self> {
+        Self::create_in(&std::env::temp_dir())
+    }
+
+    fn create_in(parent: &Path) -> Result<Self> {
+        let name = format!("{}{}", PREFIX, uuid::Uuid::new_v4());
+        // Set up under a name `sweep` ignores, so the profile is locked before it can be swept
+        let staging = parent.join(format!(".{}", name));
+        fs::create_dir_all(&staging)?;
+        let locked =
+            File::create(staging.join(LOCK_FILE)).map_err(anyhow::Error::from).and_then(|lock| {
+                lock.try_lock()
+                    .map_err(|e| anyhow!("Failed to lock profile {}: {}", staging.display(), e))?;
+                let dir = parent.join(name);
+                fs::rename(&staging, &dir)?;
+                Ok(Self { dir, _lock: lock })
+            });
+        if locked.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        locked
+    }
+
+    pub(super) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Last lines of Chrome's stderr, for error messages
+    pub(super) fn stderr_tail(&self, lines: usize) -> String {
+        let log = fs::read_to_string(self.dir.join(STDERR_LOG)).unwrap_or_default();
+        let all = log.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<_>>();
+        all[all.len().saturating_sub(lines)..].join("\n")
+    }
+}
+
+impl Drop for Profile {
+    fn drop(&mut self) {
+        // Chrome's helper processes can hold files for a moment after the browser exits
+        for _ in 0..10 {
+            if fs::remove_dir_all(&self.dir).is_ok() || !self.dir.exists() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Delete the profiles left in the temp directory by runs that crashed or were killed
+///
+/// Returns the directories removed. Profiles still locked by a running browser are kept.
+pub(super) fn sweep() -> Vec<PathBuf> {
+    sweep_in(&std::env::temp_dir())
+}
+
+fn sweep_in(parent: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|dir| is_stale(dir) && fs::remove_dir_all(dir).is_ok())
+        .collect()
+}
+
+/// Whether `dir` is a profile of ours that no browser is using
+fn is_stale(dir: &Path) -> bool {
+    let ours = dir
+        .file_name()
+        .and_then(|name| name.to_str()?.strip_prefix(PREFIX))
+        .is_some_and(|id| uuid::Uuid::parse_str(id).is_ok());
+    if !ours || !dir.is_dir() {
+        return false;
+    }
+    match File::options().write(true).open(dir.join(LOCK_FILE)) {
+        // The lock is released when its holder exits, however it exits
+        Ok(lock) => lock.try_lock().is_ok(),
+        Err(_) => {
+            dir.join(STDERR_LOG).is_file()
+                && fs::metadata(dir).and_then(|meta| meta.modified()).is_ok_and(|modified| {
+                    modified.elapsed().is_ok_and(|age| age > UNLOCKED_STALE_AFTER)
+                })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_removed_on_drop() {
+        let temp = tempfile::tempdir().unwrap();
+        let profile = Profile::create_in(temp.path()).unwrap();
+        let dir = profile.dir().to_path_buf();
+        assert!(dir.join(LOCK_FILE).is_file());
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1, "staging directory left behind");
+        fs::write(dir.join(STDERR_LOG), "one\n\ntwo\nthree\n").unwrap();
+        assert_eq!(profile.stderr_tail(2), "two\nthree");
+
+        drop(profile);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_sweep_keeps_profiles_in_use_and_unrelated_dirs() {
+        let temp = tempfile::tempdir().unwrap();
+        let in_use = Profile::create_in(temp.path()).unwrap();
+
+        // Left behind by a crashed run: the lock file exists but nobody holds it
+        let stale = temp.path().join(format!("{}{}", PREFIX, uuid::Uuid::new_v4()));
+        fs::create_dir(&stale).unwrap();
+        File::create(stale.join(LOCK_FILE)).unwrap();
+
+        // Without a lock file only old profiles are removed
+        let recent = temp.path().join(format!("{}{}", PREFIX, uuid::Uuid::new_v4()));
+        fs::create_dir(&recent).unwrap();
+        File::create(recent.join(STDERR_LOG)).unwrap();
+
+        let unrelated = temp.path().join("chrome-updater");
+        fs::create_dir(&unrelated).unwrap();
+
+        // Still being set up by `Profile::create_in`, not locked yet
+        let staging = temp.path().join(format!(".{}{}", PREFIX, uuid::Uuid::new_v4()));
+        fs::create_dir(&staging).unwrap();
+        File::create(staging.join(LOCK_FILE)).unwrap();
+
+        assert_eq!(sweep_in(temp.path()), vec![stale]);
+        assert!(in_use.dir().exists() && recent.exists() && unrelated.exists());
+        assert!(staging.exists());
+    }
+}
@@ -1,18 +1,122 @@
+use crate::backend::DEFAULT_API_BASE_URL;
+use crate::format::CiteKey;
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+/// Transport used to talk to arXiv
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Scrape arxiv.org through a headless Chrome
+    #[cfg_attr(feature = "browser", default)]
+    Browser,
+    /// Query the arXiv Atom export API over plain HTTP
+    #[cfg_attr(not(feature = "browser"), default)]
+    Api,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Browser => write!(f, "browser"),
+            Backend::Api => write!(f, "api"),
+        }
+    }
+}
+
+/// Headless implementation Chrome runs with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadlessMode {
+    /// The full browser without a window (`--headless=new`)
+    #[default]
+    New,
+    /// The separate legacy headless implementation (`--headless=old`)
+    Old,
+}
+
+impl std::fmt::Display for HeadlessMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadlessMode::New => write!(f, "new"),
+            HeadlessMode::Old => write!(f, "old"),
+        }
+    }
+}
+
+/// Whether Chrome runs with its sandbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandbox {
+    /// Disable the sandbox only when running as root, where Chrome refuses to start with it
+    #[default]
+    Auto,
+    Enabled,
+    /// Pass `--no-sandbox`
+    Disabled,
+}
+
+impl std::fmt::Display for Sandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sandbox::Auto => write!(f, "auto"),
+            Sandbox::Enabled => write!(f, "enabled"),
+            Sandbox::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub headless: bool,
+    /// Headless implementation used when `headless` is set
+    pub headless_mode: HeadlessMode,
+    pub sandbox: Sandbox,
+    /// Let Chrome keep shared memory in the temp directory instead of a small `/dev/shm`
+    pub disable_dev_shm_usage: bool,
+    /// Browser window size as `WIDTHxHEIGHT`
+    pub window_size: Option<String>,
+    /// User-agent of the browser, a desktop Chrome one when unset
+    pub user_agent: Option<String>,
+    /// Extra command-line arguments passed to Chrome
+    pub chrome_args: Vec<String>,
     pub browser_path: Option<String>,
+    /// DevTools address of a running browser to use instead of launching one, either
+    /// `http://host:port` or a `ws://` browser endpoint
+    pub browser_url: Option<String>,
+    pub backend: Backend,
+    pub api_base_url: String,
+    /// Citation key scheme used by the BibTeX output
+    pub cite_key: CiteKey,
+    /// Search pages the browser backend loads in parallel, each in its own tab
+    pub concurrency: usize,
+    /// Minimum delay in milliseconds between two page loads of the browser backend
+    pub request_delay_ms: u64,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { headless: true, browser_path: None }
+        Self {
+            headless: true,
+            headless_mode: HeadlessMode::default(),
+            sandbox: Sandbox::default(),
+            disable_dev_shm_usage: false,
+            window_size: None,
+            user_agent: None,
+            chrome_args: Vec::new(),
+            browser_path: None,
+            browser_url: None,
+            backend: Backend::default(),
+            api_base_url: DEFAULT_API_BASE_URL.to_string(),
+            cite_key: CiteKey::default(),
+            concurrency: 4,
+            request_delay_ms: 1000,
+        }
     }
 }
 
@@ -27,9 +131,18 @@ impl Config {
         let content = fs::read_to_string(&config_path)
             .with_context(|| format!("Failed to read config file at {:?}", config_path))?;
 
-        let config: Config =
-            serde_json::from_str(&content).with_context(|| "Failed to parse config file")?;
+        Self::parse(&content)
+            .with_context(|| format!("Failed to load config file at {:?}", config_path))
+    }
 
+    /// Parse the JSON config, checking values `config set` would have rejected
+    fn parse(content: &str) -> Result<Self> {
+        let mut config: Config = serde_json::from_str(content)?;
+        // Keys whose values serde accepts but `set` checks or normalises further
+        for key in ["window_size", "browser_url", "api_base_url", "concurrency"] {
+            let value = config.get(key)?;
+            config.set(key, &value)?;
+        }
         Ok(config)
     }
 
@@ -59,18 +172,121 @@ impl Config {
             "headless" => {
                 self.headless = value.parse().with_context(|| "Invalid boolean for headless")?;
             }
+            "headless_mode" => {
+                self.headless_mode = HeadlessMode::from_str(value, true).map_err(|_| {
+                    anyhow::anyhow!("Invalid headless_mode: {} (expected new or old)", value)
+                })?;
+            }
+            "sandbox" => {
+                self.sandbox = Sandbox::from_str(value, true).map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid sandbox: {} (expected auto, enabled or disabled)",
+                        value
+                    )
+                })?;
+            }
+            "disable_dev_shm_usage" => {
+                self.disable_dev_shm_usage =
+                    value.parse().with_context(|| "Invalid boolean for disable_dev_shm_usage")?;
+            }
+            "window_size" => {
+                self.window_size = match value {
+                    "" => None,
+                    size => Some(Self::parse_window_size(size)?),
+                };
+            }
+            "user_agent" => {
+                self.user_agent = if value.is_empty() { None } else { Some(value.to_string()) };
+            }
+            "chrome_args" => {
+                self.chrome_args = value.split_whitespace().map(String::from).collect();
+            }
             "browser_path" => {
                 self.browser_path = if value.is_empty() { None } else { Some(value.to_string()) };
             }
+            "browser_url" => {
+                self.browser_url = match value {
+                    "" => None,
+                    url => Some(Self::parse_browser_url(url)?),
+                };
+            }
+            "backend" => {
+                self.backend = Backend::from_str(value, true).map_err(|_| {
+                    anyhow::anyhow!("Invalid backend: {} (expected browser or api)", value)
+                })?;
+            }
+            "api_base_url" => {
+                self.api_base_url = if value.is_empty() {
+                    DEFAULT_API_BASE_URL.to_string()
+                } else {
+                    value.to_string()
+                };
+            }
+            "cite_key" => {
+                self.cite_key = CiteKey::from_str(value, true).map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid cite_key: {} (expected author-year-word, author-year or arxiv-id)",
+                        value
+                    )
+                })?;
+            }
+            "concurrency" => {
+                self.concurrency = value.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid concurrency: {} (expected 1 or more)", value)
+                })?;
+            }
+            "request_delay_ms" => {
+                self.request_delay_ms =
+                    value.parse().with_context(|| "Invalid number for request_delay_ms")?;
+            }
             _ => anyhow::bail!("Unknown config key: {}", key),
         }
         Ok(())
     }
 
+    /// Normalise a `WIDTHxHEIGHT` (or `WIDTH,HEIGHT`) window size to `WIDTHxHEIGHT`
+    pub fn parse_window_size(size: &str) -> Result<String> {
+        let dimensions = size
+            .split_once(['x', 'X', ','])
+            .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?)))
+            .filter(|&(w, h)| w > 0 && h > 0);
+        match dimensions {
+            Some((width, height)) => Ok(format!("{}x{}", width, height)),
+            None => anyhow::bail!(
+                "Invalid window_size: {} (expected WIDTHxHEIGHT, e.g. 1280x800)",
+                size
+            ),
+        }
+    }
+
+    /// Check that `url` is an `http(s)://` or `ws(s)://` DevTools address
+    pub fn parse_browser_url(url: &str) -> Result<String> {
+        let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+        match scheme.as_deref() {
+            Some("http" | "https" | "ws" | "wss") => Ok(url.to_string()),
+            _ => anyhow::bail!(
+                "Invalid browser_url: {} (expected http://host:port or a ws:// endpoint)",
+                url
+            ),
+        }
+    }
+
     pub fn get(&self, key: &str) -> Result<String> {
         match key {
             "headless" => Ok(self.headless.to_string()),
+            "headless_mode" => Ok(self.headless_mode.to_string()),
+            "sandbox" => Ok(self.sandbox.to_string()),
+            "disable_dev_shm_usage" => Ok(self.disable_dev_shm_usage.to_string()),
+            "window_size" => Ok(self.window_size.clone().unwrap_or_default()),
+            "user_agent" => Ok(self.user_agent.clone().unwrap_or_default()),
+            "chrome_args" => Ok(self.chrome_args.join(" ")),
             "browser_path" => Ok(self.browser_path.clone().unwrap_or_default()),
+            "browser_url" => Ok(self.browser_url.clone().unwrap_or_default()),
+            "backend" => Ok(self.backend.to_string()),
+            "api_base_url" => Ok(self.api_base_url.clone()),
+            "cite_key" => Ok(self.cite_key.to_string()),
+            "concurrency" => Ok(self.concurrency.to_string()),
+            "request_delay_ms" => Ok(self.request_delay_ms.to_string()),
             _ => anyhow::bail!("Unknown config key: {}", key),
         }
     }
@@ -87,6 +303,123 @@ mod tests {
         let config = Config::default();
         assert!(config.headless);
         assert!(config.browser_path.is_none());
+        assert_eq!(config.backend, Backend::default());
+        assert_eq!(config.api_base_url, DEFAULT_API_BASE_URL);
+    }
+
+    #[test]
+    fn test_config_backend_set_get() {
+        let mut config = Config::default();
+
+        config.set("backend", "api").unwrap();
+        assert_eq!(config.backend, Backend::Api);
+        assert_eq!(config.get("backend").unwrap(), "api");
+        assert!(config.set("backend", "carrier-pigeon").is_err());
+
+        config.set("api_base_url", "http://127.0.0.1:8080/api/query").unwrap();
+        assert_eq!(config.get("api_base_url").unwrap(), "http://127.0.0.1:8080/api/query");
+        config.set("api_base_url", "").unwrap();
+        assert_eq!(config.api_base_url, DEFAULT_API_BASE_URL);
+    }
+
+    #[test]
+    fn test_config_cite_key_set_get() {
+        let mut config = Config::default();
+        assert_eq!(config.get("cite_key").unwrap(), "author-year-word");
+
+        config.set("cite_key", "arxiv-id").unwrap();
+        assert_eq!(config.cite_key, CiteKey::ArxivId);
+        assert_eq!(config.get("cite_key").unwrap(), "arxiv-id");
+        assert!(config.set("cite_key", "title").is_err());
+    }
+
+    #[test]
+    fn test_config_concurrency_set_get() {
+        let mut config = Config::default();
+        assert_eq!(config.get("concurrency").unwrap(), "4");
+        assert_eq!(config.get("request_delay_ms").unwrap(), "1000");
+
+        config.set("concurrency", "8").unwrap();
+        config.set("request_delay_ms", "250").unwrap();
+        assert_eq!((config.concurrency, config.request_delay_ms), (8, 250));
+        assert!(config.set("concurrency", "0").is_err());
+        assert!(config.set("request_delay_ms", "soon").is_err());
+    }
+
+    #[test]
+    fn test_config_browser_url_set_get() {
+        let mut config = Config::default();
+        assert_eq!(config.get("browser_url").unwrap(), "");
+
+        config.set("browser_url", "http://chromium:9222").unwrap();
+        assert_eq!(config.browser_url.as_deref(), Some("http://chromium:9222"));
+        config.set("browser_url", "ws://127.0.0.1:9222/devtools/browser/abc").unwrap();
+        assert_eq!(config.get("browser_url").unwrap(), "ws://127.0.0.1:9222/devtools/browser/abc");
+        assert!(config.set("browser_url", "chromium:9222").is_err());
+
+        config.set("browser_url", "").unwrap();
+        assert!(config.browser_url.is_none());
+    }
+
+    #[test]
+    fn test_config_chrome_launch_set_get() {
+        let mut config = Config::default();
+        assert_eq!(config.get("headless_mode").unwrap(), "new");
+        assert_eq!(config.get("sandbox").unwrap(), "auto");
+
+        config.set("headless_mode", "old").unwrap();
+        config.set("sandbox", "disabled").unwrap();
+        config.set("disable_dev_shm_usage", "true").unwrap();
+        config.set("window_size", "1920,1080").unwrap();
+        config.set("user_agent", "arxiv-cli/1.0").unwrap();
+        config.set("chrome_args", "--lang=en-US  --mute-audio").unwrap();
+        assert_eq!(config.headless_mode, HeadlessMode::Old);
+        assert_eq!(config.sandbox, Sandbox::Disabled);
+        assert!(config.disable_dev_shm_usage);
+        assert_eq!(config.get("window_size").unwrap(), "1920x1080");
+        assert_eq!(config.get("user_agent").unwrap(), "arxiv-cli/1.0");
+        assert_eq!(config.chrome_args, ["--lang=en-US", "--mute-audio"]);
+        assert_eq!(config.get("chrome_args").unwrap(), "--lang=en-US --mute-audio");
+
+        assert!(config.set("headless_mode", "invisible").is_err());
+        assert!(config.set("sandbox", "maybe").is_err());
+        assert!(config.set("window_size", "1280").is_err());
+        assert!(config.set("window_size", "0x800").is_err());
+
+        config.set("window_size", "").unwrap();
+        config.set("user_agent", "").unwrap();
+        config.set("chrome_args", "").unwrap();
+        assert!(config.window_size.is_none() && config.user_agent.is_none());
+        assert!(config.chrome_args.is_empty());
+    }
+
+    #[test]
+    fn test_config_missing_keys_use_defaults() {
+        let config: Config =
+            serde_json::from_str(r#"{"headless": false, "browser_path": null}"#).unwrap();
+        assert!(!config.headless);
+        assert_eq!(config.backend, Backend::default());
+    }
+
+    #[test]
+    fn test_config_parse_validates_window_size() {
+        let config = Config::parse(r#"{"window_size": "1280, 800"}"#).unwrap();
+        assert_eq!(config.window_size.as_deref(), Some("1280x800"));
+
+        let error = Config::parse(r#"{"window_size": "large"}"#).unwrap_err();
+        assert!(error.to_string().contains("Invalid window_size: large"), "{}", error);
+    }
+
+    #[test]
+    fn test_config_parse_validates_like_set() {
+        let config = Config::parse(r#"{"browser_url": "", "api_base_url": ""}"#).unwrap();
+        assert!(config.browser_url.is_none());
+        assert_eq!(config.api_base_url, DEFAULT_API_BASE_URL);
+
+        let error = Config::parse(r#"{"browser_url": "localhost:9222"}"#).unwrap_err();
+        assert!(error.to_string().contains("Invalid browser_url"), "{}", error);
+        let error = Config::parse(r#"{"concurrency": 0}"#).unwrap_err();
+        assert!(error.to_string().contains("Invalid concurrency"), "{}", error);
     }
 
     #[test]
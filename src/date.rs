@@ -0,0 +1,197 @@
+//! Parsing of the date formats shown by arXiv pages and feeds, and of dates given by users
+
+use crate::models::{Paper, Version};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// Formats of a single date, after version markers and sizes have been removed
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d %B, %Y", "%d %B %Y", "%d %b %Y", "%d %b, %Y"];
+
+/// Parse a single date in any of the formats used by arXiv
+///
+/// Accepts RFC 3339 timestamps from the API, `Mon, 12 Jun 2017 17:57:34 UTC` from submission
+/// histories, `17 Jun 2017` from abstract pages and `30 October, 2023` from search results,
+/// ignoring `[v1]`-style markers and parenthesised notes.
+pub(crate) fn parse(text: &str) -> Option<NaiveDate> {
+    let text = strip_notes(text);
+    let text = text.trim().trim_end_matches(['.', ';']).trim_end();
+    let lower = text.to_ascii_lowercase();
+    let text = ["submitted on ", "submitted "]
+        .iter()
+        .find_map(|prefix| lower.starts_with(prefix).then(|| text[prefix.len()..].trim()))
+        .unwrap_or(text);
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.date_naive());
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(text, "%a, %d %b %Y %H:%M:%S UTC") {
+        return Some(datetime.date());
+    }
+    DATE_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+}
+
+/// Parse a date filter given as `YYYY-MM-DD`, the only form accepted from users
+pub fn parse_ymd(text: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date {:?}, expected YYYY-MM-DD such as 2024-01-31", text))
+}
+
+/// Parse a month such as `October 2023` into ISO-8601 `2023-10`
+fn parse_month(text: &str) -> Option<String> {
+    let text = text.trim().trim_end_matches('.');
+    NaiveDate::parse_from_str(&format!("1 {}", text), "%d %B %Y")
+        .ok()
+        .map(|date| date.format("%Y-%m").to_string())
+}
+
+/// Drop `[...]` and `(...)` sections, e.g. `[v1]` or `(this version, v7)`
+fn strip_notes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Fill in the normalised dates of a paper that are still unset
+///
+/// Uses the submission history when present and otherwise the raw date text, which is either
+/// a search result line (`Submitted 30 October, 2023; v1 submitted 27 October, 2023;
+/// originally announced October 2023.`) or an abstract page dateline (`[Submitted on
+/// 12 Jun 2017 (v1), last revised 2 Aug 2023 (this version, v7)]`).
+pub(crate) fn normalize(paper: &mut Paper) {
+    let raw = paper.published_date.trim();
+    let raw = raw.strip_prefix(['[', '(']).and_then(|r| r.strip_suffix([']', ')'])).unwrap_or(raw);
+    let raw = raw.replace(", last revised", "; last revised");
+
+    let (mut original, mut latest, mut announced) = (None, None, None);
+    for segment in raw.split(';').map(str::trim) {
+        let lower = segment.to_ascii_lowercase();
+        let after = |prefix: &str| lower.starts_with(prefix).then(|| &segment[prefix.len()..]);
+        if let Some(rest) = after("v1 submitted ").or_else(|| after("submitted on ")) {
+            original = parse(rest);
+        } else if let Some(rest) = after("submitted ").or_else(|| after("last revised ")) {
+            latest = parse(rest);
+        } else if let Some(rest) = after("originally announced ") {
+            announced = parse_month(rest);
+        } else if original.is_none() {
+            original = parse(segment);
+        }
+    }
+
+    let history = |version: Option<&Version>| version.and_then(|v| parse(&v.submitted));
+    paper.submitted =
+        paper.submitted.or_else(|| history(paper.versions.first())).or(original).or(latest);
+    paper.updated =
+        paper.updated.or_else(|| history(paper.versions.last())).or(latest).or(paper.submitted);
+    paper.announced = paper.announced.take().or(announced);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!(parse("2017-06-12T17:57:34Z"), date(2017, 6, 12));
+        assert_eq!(parse("2017-06-12"), date(2017, 6, 12));
+        assert_eq!(parse("30 October, 2023"), date(2023, 10, 30));
+        assert_eq!(parse("17 Jun 2017"), date(2017, 6, 17));
+        assert_eq!(parse("[v1] 17 Jun 2017"), date(2017, 6, 17));
+        assert_eq!(parse("Submitted on 17 Jun 2017"), date(2017, 6, 17));
+        assert_eq!(parse("Mon, 12 Jun 2017 17:57:34 UTC"), date(2017, 6, 12));
+        assert_eq!(parse("2 Aug 2023 (this version, v7)"), date(2023, 8, 2));
+        assert_eq!(parse("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_ymd() {
+        assert_eq!(parse_ymd("2024-01-31").ok(), date(2024, 1, 31));
+        for text in ["2024/01/31", "31 January 2024", "2024-02-30", ""] {
+            let error = parse_ymd(text).unwrap_err();
+            assert!(error.to_string().contains("expected YYYY-MM-DD"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_normalize_search_result() {
+        let mut paper = Paper {
+            published_date: "Submitted 30 October, 2023; v1 submitted 27 October, 2023; \
+                             originally announced October 2023."
+                .to_string(),
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!(paper.submitted, date(2023, 10, 27));
+        assert_eq!(paper.updated, date(2023, 10, 30));
+        assert_eq!(paper.announced.as_deref(), Some("2023-10"));
+
+        let mut paper = Paper {
+            published_date: "Submitted 30 October, 2023; originally announced November 2023."
+                .to_string(),
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!((paper.submitted, paper.updated), (date(2023, 10, 30), date(2023, 10, 30)));
+        assert_eq!(paper.announced.as_deref(), Some("2023-11"));
+    }
+
+    #[test]
+    fn test_normalize_abstract_page() {
+        let mut paper = Paper {
+            published_date: "[Submitted on 12 Jun 2017 (v1), last revised 2 Aug 2023 (this \
+                             version, v7)]"
+                .to_string(),
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!(paper.submitted, date(2017, 6, 12));
+        assert_eq!(paper.updated, date(2023, 8, 2));
+        assert_eq!(paper.announced, None);
+
+        let mut paper = Paper {
+            published_date: "(Submitted on 17 Jun 2017)".to_string(),
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!((paper.submitted, paper.updated), (date(2017, 6, 17), date(2017, 6, 17)));
+    }
+
+    #[test]
+    fn test_normalize_prefers_history_and_keeps_known_dates() {
+        let version = |version, submitted: &str| Version {
+            version,
+            submitted: submitted.to_string(),
+            size_kb: None,
+        };
+        let mut paper = Paper {
+            published_date: "17 Jun 2017".to_string(),
+            versions: vec![
+                version(1, "Mon, 12 Jun 2017 17:57:34 UTC"),
+                version(2, "Wed, 2 Aug 2023 00:41:18 UTC"),
+            ],
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!((paper.submitted, paper.updated), (date(2017, 6, 12), date(2023, 8, 2)));
+        assert_eq!(paper.published_date, "17 Jun 2017");
+
+        let mut paper = Paper {
+            published_date: "30 October, 2023".to_string(),
+            submitted: date(2020, 1, 1),
+            ..Default::default()
+        };
+        normalize(&mut paper);
+        assert_eq!((paper.submitted, paper.updated), (date(2020, 1, 1), date(2020, 1, 1)));
+    }
+}
@@ -0,0 +1,361 @@
+//! Comparison of two versions of a paper
+//!
+//! Paragraphs extracted from each version's PDF are aligned with a patience diff; replaced
+//! runs are paired up when they are similar enough to count as edits of the same paragraph.
+
+use crate::models::{Paper, Paragraph};
+use serde::Serialize;
+use similar::{Algorithm, ChangeTag, DiffOp, TextDiff};
+
+/// Word similarity above which a replaced paragraph counts as changed rather than rewritten
+const CHANGED_THRESHOLD: f32 = 0.5;
+
+/// Old and new value of a metadata field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub old: String,
+    pub new: String,
+}
+
+/// Authors present in only one of the versions
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AuthorChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// A paragraph that differs between the versions, identified by its paragraph numbers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum ParagraphChange {
+    Added { to: String, text: String },
+    Removed { from: String, text: String },
+    Changed { from: String, to: String, old: String, new: String },
+}
+
+/// Differences between two versions of a paper
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaperDiff {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub title: Option<FieldChange>,
+    #[serde(rename = "abstract")]
+    pub summary: Option<FieldChange>,
+    pub authors: AuthorChanges,
+    pub paragraphs: Vec<ParagraphChange>,
+}
+
+/// Compare the metadata and extracted paragraphs of two versions
+pub fn compare(old: &Paper, new: &Paper) -> PaperDiff {
+    let field = |old: &str, new: &str| {
+        let (old, new) = (normalize(old), normalize(new));
+        (old != new).then_some(FieldChange { old, new })
+    };
+
+    PaperDiff {
+        id: new.id.to_string(),
+        from: version_label(old),
+        to: version_label(new),
+        title: field(&old.title, &new.title),
+        summary: field(&old.summary, &new.summary),
+        authors: AuthorChanges {
+            added: new.authors.iter().filter(|a| !old.authors.contains(a)).cloned().collect(),
+            removed: old.authors.iter().filter(|a| !new.authors.contains(a)).cloned().collect(),
+        },
+        paragraphs: align_paragraphs(paragraphs(old), paragraphs(new)),
+    }
+}
+
+/// Unified line diff of both versions, one line per metadata field and paragraph
+pub fn unified(old: &Paper, new: &Paper, context: usize) -> String {
+    let (old_doc, new_doc) = (document(old), document(new));
+    TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .diff_lines(&old_doc, &new_doc)
+        .unified_diff()
+        .context_radius(context)
+        .header(&versioned_id(old), &versioned_id(new))
+        .to_string()
+}
+
+/// Word-level rendering of a diff, marking deletions as `[-…-]` and insertions as `{+…+}`
+pub fn words(diff: &PaperDiff) -> String {
+    let mut out = format!("{} {} -> {}\n", diff.id, diff.from, diff.to);
+    if let Some(title) = &diff.title {
+        out.push_str(&format!("\n@@ title @@\n{}\n", inline_words(&title.old, &title.new)));
+    }
+    if !diff.authors.added.is_empty() || !diff.authors.removed.is_empty() {
+        out.push_str("\n@@ authors @@\n");
+        for author in &diff.authors.removed {
+            out.push_str(&format!("[-{}-]\n", author));
+        }
+        for author in &diff.authors.added {
+            out.push_str(&format!("{{+{}+}}\n", author));
+        }
+    }
+    if let Some(summary) = &diff.summary {
+        out.push_str(&format!("\n@@ abstract @@\n{}\n", inline_words(&summary.old, &summary.new)));
+    }
+    for change in &diff.paragraphs {
+        match change {
+            ParagraphChange::Added { to, text } => {
+                out.push_str(&format!("\n@@ paragraph +{} @@\n{{+{}+}}\n", to, text));
+            }
+            ParagraphChange::Removed { from, text } => {
+                out.push_str(&format!("\n@@ paragraph -{} @@\n[-{}-]\n", from, text));
+            }
+            ParagraphChange::Changed { from, to, old, new } => {
+                out.push_str(&format!(
+                    "\n@@ paragraph -{} +{} @@\n{}\n",
+                    from,
+                    to,
+                    inline_words(old, new)
+                ));
+            }
+        }
+    }
+    out
+}
+
+fn inline_words(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_words(old, new);
+    let mut out = String::new();
+    let mut open: Option<ChangeTag> = None;
+    for change in diff.iter_all_changes() {
+        let tag = change.tag();
+        if open != Some(tag) {
+            close_marker(&mut out, open);
+            match tag {
+                ChangeTag::Delete => out.push_str("[-"),
+                ChangeTag::Insert => out.push_str("{+"),
+                ChangeTag::Equal => {}
+            }
+            open = Some(tag);
+        }
+        out.push_str(change.value());
+    }
+    close_marker(&mut out, open);
+    out
+}
+
+fn close_marker(out: &mut String, tag: Option<ChangeTag>) {
+    match tag {
+        Some(ChangeTag::Delete) => out.push_str("-]"),
+        Some(ChangeTag::Insert) => out.push_str("+}"),
+        _ => {}
+    }
+}
+
+/// Pair up paragraphs of both versions and keep the ones that differ
+fn align_paragraphs(
+    old: Vec<(String, String)>,
+    new: Vec<(String, String)>,
+) -> Vec<ParagraphChange> {
+    let old_texts = old.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>();
+    let new_texts = new.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>();
+
+    let mut changes = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Patience, &old_texts, &new_texts) {
+        let (_, old_range, new_range) = op.as_tag_tuple();
+        if matches!(op, DiffOp::Equal { .. }) {
+            continue;
+        }
+
+        let (mut old_iter, mut new_iter) = (old[old_range].iter(), new[new_range].iter());
+        loop {
+            match (old_iter.next(), new_iter.next()) {
+                (Some((from, old_text)), Some((to, new_text)))
+                    if TextDiff::from_words(old_text.as_str(), new_text.as_str()).ratio()
+                        >= CHANGED_THRESHOLD =>
+                {
+                    changes.push(ParagraphChange::Changed {
+                        from: from.clone(),
+                        to: to.clone(),
+                        old: old_text.clone(),
+                        new: new_text.clone(),
+                    });
+                }
+                (Some((from, old_text)), Some((to, new_text))) => {
+                    changes.push(ParagraphChange::Removed {
+                        from: from.clone(),
+                        text: old_text.clone(),
+                    });
+                    changes.push(ParagraphChange::Added { to: to.clone(), text: new_text.clone() });
+                }
+                (Some((from, text)), None) => {
+                    changes
+                        .push(ParagraphChange::Removed { from: from.clone(), text: text.clone() });
+                }
+                (None, Some((to, text))) => {
+                    changes.push(ParagraphChange::Added { to: to.clone(), text: text.clone() });
+                }
+                (None, None) => break,
+            }
+        }
+    }
+    changes
+}
+
+/// Numbered paragraphs with whitespace normalised, as PDF extraction breaks lines arbitrarily
+fn paragraphs(paper: &Paper) -> Vec<(String, String)> {
+    paper
+        .description_paragraphs
+        .iter()
+        .flatten()
+        .map(|Paragraph { number, text, .. }| (number.clone(), normalize(text)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect()
+}
+
+/// Line-per-item rendering used by the unified diff
+fn document(paper: &Paper) -> String {
+    let mut lines = vec![
+        format!("Title: {}", normalize(&paper.title)),
+        format!("Authors: {}", paper.authors.join(", ")),
+        format!("Abstract: {}", normalize(&paper.summary)),
+        String::new(),
+    ];
+    lines.extend(paragraphs(paper).into_iter().map(|(_, text)| text));
+    lines.join("\n") + "\n"
+}
+
+fn versioned_id(paper: &Paper) -> String {
+    match paper.version {
+        Some(version) => paper.id.with_version(version).to_string(),
+        None => paper.id.to_string(),
+    }
+}
+
+fn version_label(paper: &Paper) -> String {
+    paper.version.map_or_else(|| "latest".to_string(), |v| format!("v{}", v))
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: u32, title: &str, authors: &[&str], paragraphs: &[&str]) -> Paper {
+        Paper {
+            id: "2301.00001".parse().unwrap(),
+            version: Some(version),
+            title: title.to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            summary: "We study things.".to_string(),
+            description_paragraphs: Some(
+                paragraphs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, text)| Paragraph {
+                        number: format!("{:04}", i + 1),
+                        id: String::new(),
+                        text: text.to_string(),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn sample() -> (Paper, Paper) {
+        let old = version(
+            1,
+            "A Study",
+            &["Ada Lovelace", "Alan Turing"],
+            &[
+                "Introduction to the problem.",
+                "We use a   simple\nbaseline model with five layers.",
+                "Results are preliminary.",
+                "Conclusion.",
+            ],
+        );
+        let new = version(
+            3,
+            "A Careful Study",
+            &["Ada Lovelace", "Grace Hopper"],
+            &[
+                "Introduction to the problem.",
+                "We use a simple baseline model with six layers.",
+                "An entirely new ablation section appears here.",
+                "Conclusion.",
+            ],
+        );
+        (old, new)
+    }
+
+    #[test]
+    fn test_compare_metadata_and_paragraphs() {
+        let (old, new) = sample();
+        let diff = compare(&old, &new);
+        assert_eq!((diff.from.as_str(), diff.to.as_str()), ("v1", "v3"));
+        assert_eq!(
+            diff.title,
+            Some(FieldChange { old: "A Study".into(), new: "A Careful Study".into() })
+        );
+        assert!(diff.summary.is_none());
+        assert_eq!(diff.authors.added, vec!["Grace Hopper"]);
+        assert_eq!(diff.authors.removed, vec!["Alan Turing"]);
+        assert_eq!(
+            diff.paragraphs,
+            vec![
+                ParagraphChange::Changed {
+                    from: "0002".into(),
+                    to: "0002".into(),
+                    old: "We use a simple baseline model with five layers.".into(),
+                    new: "We use a simple baseline model with six layers.".into(),
+                },
+                ParagraphChange::Removed {
+                    from: "0003".into(),
+                    text: "Results are preliminary.".into()
+                },
+                ParagraphChange::Added {
+                    to: "0003".into(),
+                    text: "An entirely new ablation section appears here.".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identical_versions_have_no_changes() {
+        let (old, _) = sample();
+        let diff = compare(&old, &old);
+        assert!(diff.title.is_none() && diff.paragraphs.is_empty());
+        assert_eq!(diff.authors, AuthorChanges::default());
+    }
+
+    #[test]
+    fn test_unified() {
+        let (old, new) = sample();
+        let out = unified(&old, &new, 0);
+        assert!(out.starts_with("--- 2301.00001v1\n+++ 2301.00001v3\n"));
+        assert!(out.contains("-Title: A Study\n-Authors: Ada Lovelace, Alan Turing\n"));
+        assert!(out.contains("+Title: A Careful Study\n"));
+        assert!(out.contains("-We use a simple baseline model with five layers.\n"));
+        assert!(out.contains("+An entirely new ablation section appears here.\n"));
+        assert!(!out.contains("Conclusion."));
+    }
+
+    #[test]
+    fn test_words() {
+        let (old, new) = sample();
+        let out = words(&compare(&old, &new));
+        assert!(out.contains("@@ title @@\nA {+Careful +}Study\n"));
+        assert!(out.contains("[-Alan Turing-]\n{+Grace Hopper+}\n"));
+        assert!(out.contains("model with [-five-]{+six+} layers."));
+        assert!(out.contains("@@ paragraph -0003 @@\n[-Results are preliminary.-]"));
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let (old, new) = sample();
+        let json = serde_json::to_value(compare(&old, &new)).unwrap();
+        assert_eq!(json["paragraphs"][0]["change"], "changed");
+        assert_eq!(json["paragraphs"][1]["change"], "removed");
+        assert!(json["abstract"].is_null());
+    }
+}
@@ -0,0 +1,310 @@
+//! BibTeX export of [`Paper`] metadata
+
+use super::{Formatter, published_date, split_name};
+use crate::models::Paper;
+use anyhow::Result;
+use chrono::Datelike;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+
+/// Words skipped when picking the title word of a citation key
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "on", "of", "in", "for", "to", "and", "with", "from", "by", "at", "is", "are",
+];
+
+/// Scheme used to derive citation keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CiteKey {
+    /// First author's surname, year and first significant title word, e.g. `vaswani2017attention`
+    #[default]
+    AuthorYearWord,
+    /// First author's surname and year, e.g. `vaswani2017`
+    AuthorYear,
+    /// The arXiv identifier, e.g. `1706.03762`
+    ArxivId,
+}
+
+impl std::fmt::Display for CiteKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CiteKey::AuthorYearWord => write!(f, "author-year-word"),
+            CiteKey::AuthorYear => write!(f, "author-year"),
+            CiteKey::ArxivId => write!(f, "arxiv-id"),
+        }
+    }
+}
+
+/// Formats papers as BibTeX entries, keeping citation keys unique across calls
+#[derive(Debug, Default)]
+pub struct BibtexWriter {
+    scheme: CiteKey,
+    used_keys: HashSet<String>,
+}
+
+impl BibtexWriter {
+    pub fn new(scheme: CiteKey) -> Self {
+        Self { scheme, used_keys: HashSet::new() }
+    }
+
+    /// Render one entry; `@article` when a journal reference is known, `@misc` otherwise
+    pub fn entry(&mut self, paper: &Paper) -> String {
+        let key = self.unique_key(paper);
+        let kind = if paper.journal_ref.is_some() { "article" } else { "misc" };
+
+        let mut fields = vec![
+            // Braced so styles keep the capitalisation of acronyms and names such as "BERT"
+            ("title", format!("{{{}}}", escape_latex(&paper.title))),
+            (
+                "author",
+                paper.authors.iter().map(|a| escape_latex(a)).collect::<Vec<_>>().join(" and "),
+            ),
+        ];
+        if let Some(year) = published_date(paper).map(|date| date.year()) {
+            fields.push(("year", year.to_string()));
+        }
+        fields.push(("eprint", paper.id.to_string()));
+        fields.push(("archivePrefix", "arXiv".to_string()));
+        if let Some(primary) = &paper.primary_category {
+            fields.push(("primaryClass", primary.clone()));
+        }
+        if let Some(journal) = &paper.journal_ref {
+            fields.push(("journal", escape_latex(journal)));
+        }
+        if let Some(doi) = &paper.doi {
+            fields.push(("doi", doi.clone()));
+        }
+        fields.push(("url", paper.url.clone()));
+
+        let mut entry = format!("@{}{{{},\n", kind, key);
+        for (name, value) in fields {
+            entry.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+        entry.push_str("}\n");
+        entry
+    }
+
+    fn unique_key(&mut self, paper: &Paper) -> String {
+        let base = cite_key(paper, self.scheme);
+        let mut key = base.clone();
+        let mut n = 1;
+        while !self.used_keys.insert(key.clone()) {
+            n += 1;
+            key = match char::from_u32('a' as u32 + n - 1) {
+                Some(c) if c <= 'z' => format!("{}{}", base, c),
+                _ => format!("{}-{}", base, n),
+            };
+        }
+        key
+    }
+}
+
+impl Formatter for BibtexWriter {
+    /// Entries are separated by blank lines
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        let separator = if self.used_keys.is_empty() { "" } else { "\n" };
+        Ok(format!("{}{}", separator, self.entry(paper)))
+    }
+}
+
+/// Derive the citation key of a paper, before de-duplication
+pub fn cite_key(paper: &Paper, scheme: CiteKey) -> String {
+    if scheme == CiteKey::ArxivId {
+        return paper.id.to_string();
+    }
+
+    let surname = paper
+        .authors
+        .first()
+        .map(|name| ascii_key_part(&split_name(name).1))
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "anon".to_string());
+    let year = published_date(paper).map(|date| date.year().to_string()).unwrap_or_default();
+    let word = match scheme {
+        CiteKey::AuthorYearWord => paper
+            .title
+            .split_whitespace()
+            .map(ascii_key_part)
+            .find(|w| !w.is_empty() && !STOP_WORDS.contains(&w.as_str()))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    format!("{}{}{}", surname, year, word)
+}
+
+/// Escape text for use in a BibTeX field
+///
+/// TeX special characters are escaped and accented letters become accent commands, while inline
+/// math between `$` signs (common in arXiv titles) is kept verbatim.
+pub fn escape_latex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('$') {
+        let Some(len) = rest[open + 1..].find('$') else { break };
+        escape_text(&rest[..open], &mut out);
+        let close = open + 1 + len;
+        out.push_str(&rest[open..=close]);
+        rest = &rest[close + 1..];
+    }
+    escape_text(rest, &mut out);
+    out
+}
+
+fn escape_text(text: &str, out: &mut String) {
+    let mut chars = text.nfd().peekable();
+    while let Some(c) = chars.next() {
+        let accent = chars.peek().copied().and_then(accent_command);
+        if let Some(accent) = accent
+            && c.is_ascii_alphabetic()
+        {
+            chars.next();
+            let base = match c {
+                'i' => "\\i".to_string(),
+                'j' => "\\j".to_string(),
+                _ => c.to_string(),
+            };
+            if accent.chars().all(|a| a.is_ascii_alphabetic()) {
+                out.push_str(&format!("{{\\{}{{{}}}}}", accent, base));
+            } else {
+                out.push_str(&format!("{{\\{}{}}}", accent, base));
+            }
+            continue;
+        }
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '&' | '%' | '#' | '_' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            'ß' => out.push_str("{\\ss}"),
+            'ø' => out.push_str("{\\o}"),
+            'Ø' => out.push_str("{\\O}"),
+            'ł' => out.push_str("{\\l}"),
+            'Ł' => out.push_str("{\\L}"),
+            'æ' => out.push_str("{\\ae}"),
+            'Æ' => out.push_str("{\\AE}"),
+            'œ' => out.push_str("{\\oe}"),
+            'Œ' => out.push_str("{\\OE}"),
+            'ı' => out.push_str("{\\i}"),
+            '–' => out.push_str("--"),
+            '—' => out.push_str("---"),
+            '‘' => out.push('`'),
+            '’' => out.push('\''),
+            '“' => out.push_str("``"),
+            '”' => out.push_str("''"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// TeX accent command for a Unicode combining mark
+fn accent_command(mark: char) -> Option<&'static str> {
+    Some(match mark {
+        '\u{0300}' => "`",
+        '\u{0301}' => "'",
+        '\u{0302}' => "^",
+        '\u{0303}' => "~",
+        '\u{0304}' => "=",
+        '\u{0306}' => "u",
+        '\u{0307}' => ".",
+        '\u{0308}' => "\"",
+        '\u{030A}' => "r",
+        '\u{030B}' => "H",
+        '\u{030C}' => "v",
+        '\u{0327}' => "c",
+        '\u{0328}' => "k",
+        _ => return None,
+    })
+}
+
+/// Lowercase ASCII letters and digits of a word, with diacritics stripped
+fn ascii_key_part(word: &str) -> String {
+    word.nfd().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper() -> Paper {
+        Paper {
+            id: "1706.03762".parse().unwrap(),
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Ashish Vaswani".to_string(), "Łukasz Kaiser".to_string()],
+            published_date: "2017-06-12T17:57:34Z".to_string(),
+            url: "https://arxiv.org/abs/1706.03762".to_string(),
+            primary_category: Some("cs.CL".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_escape_latex() {
+        assert_eq!(escape_latex("R&D at 100% _fast_"), "R\\&D at 100\\% \\_fast\\_");
+        assert_eq!(
+            escape_latex("Gödel, Erdős and Núñez"),
+            "G{\\\"o}del, Erd{\\H{o}}s and N{\\'u}{\\~n}ez"
+        );
+        assert_eq!(escape_latex("Łukasz Çelik"), "{\\L}ukasz {\\c{C}}elik");
+        assert_eq!(escape_latex("$O(n^2)$ sorting for $5"), "$O(n^2)$ sorting for \\$5");
+        assert_eq!(escape_latex("a~b{c}"), "a\\textasciitilde{}b\\{c\\}");
+    }
+
+    #[test]
+    fn test_cite_key_schemes() {
+        let paper = paper();
+        assert_eq!(cite_key(&paper, CiteKey::AuthorYearWord), "vaswani2017attention");
+        assert_eq!(cite_key(&paper, CiteKey::AuthorYear), "vaswani2017");
+        assert_eq!(cite_key(&paper, CiteKey::ArxivId), "1706.03762");
+
+        let paper = Paper {
+            title: "On the Müller Transform".to_string(),
+            authors: vec!["José Núñez".to_string()],
+            published_date: "30 October, 2023".to_string(),
+            ..paper
+        };
+        assert_eq!(cite_key(&paper, CiteKey::AuthorYearWord), "nunez2023muller");
+
+        // The surname keeps its particles, as in the author lists of the other formats
+        let paper = Paper { authors: vec!["Ludwig van Beethoven".to_string()], ..paper };
+        assert_eq!(cite_key(&paper, CiteKey::AuthorYear), "vanbeethoven2023");
+    }
+
+    #[test]
+    fn test_misc_entry() {
+        let bib = BibtexWriter::new(CiteKey::AuthorYearWord).format(&[paper()]).unwrap();
+        assert_eq!(
+            bib,
+            "@misc{vaswani2017attention,\n\
+             \x20 title = {{Attention Is All You Need}},\n\
+             \x20 author = {Ashish Vaswani and {\\L}ukasz Kaiser},\n\
+             \x20 year = {2017},\n\
+             \x20 eprint = {1706.03762},\n\
+             \x20 archivePrefix = {arXiv},\n\
+             \x20 primaryClass = {cs.CL},\n\
+             \x20 url = {https://arxiv.org/abs/1706.03762},\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_article_entry_and_unique_keys() {
+        let published = Paper {
+            journal_ref: Some("Advances in NIPS 30 & more".to_string()),
+            doi: Some("10.5555/3295222".to_string()),
+            ..paper()
+        };
+        let mut writer = BibtexWriter::new(CiteKey::AuthorYear);
+        let first = writer.entry(&published);
+        assert!(first.starts_with("@article{vaswani2017,"));
+        assert!(first.contains("  journal = {Advances in NIPS 30 \\& more},\n"));
+        assert!(first.contains("  doi = {10.5555/3295222},\n"));
+
+        assert!(writer.entry(&paper()).starts_with("@misc{vaswani2017b,"));
+        assert!(writer.entry(&paper()).starts_with("@misc{vaswani2017c,"));
+    }
+}
@@ -0,0 +1,105 @@
+//! CSL-JSON export, as imported by Zotero and Mendeley
+
+use super::{Formatter, json_array_end, json_array_item, published_date, single_line, split_name};
+use crate::models::Paper;
+use anyhow::Result;
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+
+/// Writes a CSL-JSON array; preprints are `article` items published by arXiv, papers with a
+/// journal reference are `article-journal`
+#[derive(Debug, Default)]
+pub struct CslJsonWriter {
+    count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CslItem {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    title: String,
+    author: Vec<CslName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issued: Option<CslDate>,
+    #[serde(rename = "abstract")]
+    abstract_: String,
+    #[serde(rename = "container-title", skip_serializing_if = "Option::is_none")]
+    container_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher: Option<String>,
+    number: String,
+    #[serde(rename = "DOI", skip_serializing_if = "Option::is_none")]
+    doi: Option<String>,
+    #[serde(rename = "URL")]
+    url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CslName {
+    family: String,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    given: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CslDate {
+    #[serde(rename = "date-parts")]
+    date_parts: Vec<Vec<i32>>,
+}
+
+impl From<&Paper> for CslItem {
+    fn from(paper: &Paper) -> Self {
+        let journal = paper.journal_ref.as_deref().map(single_line);
+        CslItem {
+            id: paper.id.to_string(),
+            kind: if journal.is_some() { "article-journal" } else { "article" }.to_string(),
+            title: single_line(&paper.title),
+            author: paper
+                .authors
+                .iter()
+                .map(|name| {
+                    let (given, family) = split_name(name);
+                    CslName { family, given }
+                })
+                .collect(),
+            issued: published_date(paper).map(|date| CslDate {
+                date_parts: vec![vec![date.year(), date.month() as i32, date.day() as i32]],
+            }),
+            abstract_: single_line(&paper.summary),
+            publisher: if journal.is_none() { Some("arXiv".to_string()) } else { None },
+            container_title: journal,
+            number: format!("arXiv:{}", paper.id),
+            doi: paper.doi.clone(),
+            url: paper.url.clone(),
+        }
+    }
+}
+
+impl Formatter for CslJsonWriter {
+    fn begin(&mut self) -> Result<String> {
+        Ok("[".to_string())
+    }
+
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        let json = serde_json::to_string_pretty(&CslItem::from(paper))?;
+        let item = json_array_item(self.count, &json);
+        self.count += 1;
+        Ok(item)
+    }
+
+    fn end(&mut self) -> Result<String> {
+        Ok(json_array_end(self.count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_output_is_valid_json() {
+        let output = CslJsonWriter::default().format(&[]).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<CslItem>>(&output).unwrap(), []);
+    }
+}
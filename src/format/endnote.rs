@@ -0,0 +1,110 @@
+//! EndNote XML export
+
+use super::{Formatter, published_date, single_line, split_name};
+use crate::models::Paper;
+use anyhow::Result;
+use quick_xml::escape::escape;
+
+/// Writes an EndNote XML document; preprints are Electronic Articles, papers with a journal
+/// reference Journal Articles
+#[derive(Debug, Default)]
+pub struct EndnoteXmlWriter;
+
+impl Formatter for EndnoteXmlWriter {
+    fn begin(&mut self) -> Result<String> {
+        Ok("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xml>\n  <records>\n".to_string())
+    }
+
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        let text = |value: &str| escape(single_line(value)).into_owned();
+
+        let mut record = String::from("    <record>\n");
+        let (ref_type, name) = match paper.journal_ref {
+            Some(_) => (17, "Journal Article"),
+            None => (43, "Electronic Article"),
+        };
+        record.push_str(&format!("      <ref-type name=\"{}\">{}</ref-type>\n", name, ref_type));
+
+        record.push_str("      <contributors>\n        <authors>\n");
+        for author in &paper.authors {
+            let name = match split_name(author) {
+                (given, family) if given.is_empty() => family,
+                (given, family) => format!("{}, {}", family, given),
+            };
+            record.push_str(&format!("          <author>{}</author>\n", text(&name)));
+        }
+        record.push_str("        </authors>\n      </contributors>\n");
+
+        record
+            .push_str(&format!("      <titles>\n        <title>{}</title>\n", text(&paper.title)));
+        if let Some(journal) = &paper.journal_ref {
+            record.push_str(&format!(
+                "        <secondary-title>{}</secondary-title>\n",
+                text(journal)
+            ));
+        }
+        record.push_str("      </titles>\n");
+        if let Some(journal) = &paper.journal_ref {
+            record.push_str(&format!(
+                "      <periodical>\n        <full-title>{}</full-title>\n      </periodical>\n",
+                text(journal)
+            ));
+        }
+
+        if let Some(date) = published_date(paper) {
+            record.push_str(&format!(
+                "      <dates>\n        <year>{}</year>\n        <pub-dates>\n          <date>{}</date>\n        </pub-dates>\n      </dates>\n",
+                date.format("%Y"),
+                date.format("%Y-%m-%d")
+            ));
+        }
+        if paper.journal_ref.is_none() {
+            record.push_str("      <publisher>arXiv</publisher>\n");
+        }
+        record.push_str(&format!(
+            "      <accession-num>arXiv:{}</accession-num>\n",
+            text(&paper.id.to_string())
+        ));
+        if let Some(primary) = &paper.primary_category {
+            record.push_str(&format!(
+                "      <keywords>\n        <keyword>{}</keyword>\n      </keywords>\n",
+                text(primary)
+            ));
+        }
+        record.push_str(&format!("      <abstract>{}</abstract>\n", text(&paper.summary)));
+        if let Some(doi) = &paper.doi {
+            record.push_str(&format!(
+                "      <electronic-resource-num>{}</electronic-resource-num>\n",
+                text(doi)
+            ));
+        }
+        record.push_str(&format!(
+            "      <urls>\n        <related-urls>\n          <url>{}</url>\n        </related-urls>\n        <pdf-urls>\n          <url>{}</url>\n        </pdf-urls>\n      </urls>\n",
+            text(&paper.url),
+            text(&paper.pdf_url)
+        ));
+        record.push_str("    </record>\n");
+        Ok(record)
+    }
+
+    fn end(&mut self) -> Result<String> {
+        Ok("  </records>\n</xml>\n".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::sample_papers;
+    use quick_xml::escape::unescape;
+
+    #[test]
+    fn test_xml_escaping() {
+        let paper = &sample_papers()[1];
+        let output = EndnoteXmlWriter.format(std::slice::from_ref(paper)).unwrap();
+        let title = output.split_once("<title>").and_then(|(_, rest)| rest.split_once("</title>"));
+        let title = title.unwrap().0;
+        assert_eq!(title, "Evaluating &lt;LLMs&gt; on R&amp;D &quot;tasks&quot;");
+        assert_eq!(unescape(title).unwrap(), paper.title);
+    }
+}
@@ -0,0 +1,369 @@
+//! Output formats for [`Paper`] lists, looked up by name in a [`Registry`]
+//!
+//! Formatters are streaming: [`Formatter::begin`], one [`Formatter::paper`] call per result and
+//! [`Formatter::end`] each return a chunk of the output, so results can be written as they arrive.
+
+pub mod bibtex;
+mod csl;
+mod endnote;
+mod ris;
+
+pub use bibtex::{BibtexWriter, CiteKey};
+pub use csl::CslJsonWriter;
+pub use endnote::EndnoteXmlWriter;
+pub use ris::RisWriter;
+
+use crate::models::Paper;
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+
+/// Settings shared by all formatters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatOptions {
+    /// Citation key scheme, used by BibTeX
+    pub cite_key: CiteKey,
+}
+
+/// Turns papers into one output format
+pub trait Formatter: Send {
+    /// Output written before the first paper
+    fn begin(&mut self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    /// Output for one paper
+    fn paper(&mut self, paper: &Paper) -> Result<String>;
+
+    /// Output written after the last paper
+    fn end(&mut self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    /// Output for a single fetched paper; a one-element document unless the format has a
+    /// dedicated single-record form
+    fn single(&mut self, paper: &Paper) -> Result<String> {
+        self.format(std::slice::from_ref(paper))
+    }
+
+    /// Format a complete list of papers
+    fn format(&mut self, papers: &[Paper]) -> Result<String> {
+        let mut out = self.begin()?;
+        for paper in papers {
+            out.push_str(&self.paper(paper)?);
+        }
+        out.push_str(&self.end()?);
+        Ok(out)
+    }
+}
+
+type Factory = Box<dyn Fn(&FormatOptions) -> Box<dyn Formatter> + Send + Sync>;
+
+struct Entry {
+    name: &'static str,
+    description: &'static str,
+    factory: Factory,
+}
+
+/// Output formats keyed by the name given to `--format`
+///
+/// [`Registry::default`] holds the built-in formats; more can be added with
+/// [`Registry::register`].
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// Add a format, replacing any existing format with the same name
+    pub fn register<F>(&mut self, name: &'static str, description: &'static str, factory: F)
+    where
+        F: Fn(&FormatOptions) -> Box<dyn Formatter> + Send + Sync + 'static,
+    {
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.push(Entry { name, description, factory: Box::new(factory) });
+    }
+
+    /// Names and descriptions of the registered formats, in registration order
+    pub fn formats(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.entries.iter().map(|entry| (entry.name, entry.description))
+    }
+
+    /// Create a formatter by name
+    pub fn get(&self, name: &str, options: &FormatOptions) -> Result<Box<dyn Formatter>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+            .map(|entry| (entry.factory)(options))
+            .ok_or_else(|| {
+                let names = self.formats().map(|(name, _)| name).collect::<Vec<_>>();
+                anyhow!("Unknown output format: {} (expected one of {})", name, names.join(", "))
+            })
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("json", "Pretty-printed JSON array", |_| Box::new(JsonWriter::default()));
+        registry.register("ndjson", "One JSON object per line", |_| Box::new(NdjsonWriter));
+        registry.register("bibtex", "BibTeX entries for LaTeX", |options| {
+            Box::new(BibtexWriter::new(options.cite_key))
+        });
+        registry
+            .register("ris", "RIS records (Zotero, Mendeley, EndNote)", |_| Box::new(RisWriter));
+        registry.register("csl-json", "CSL-JSON items (Zotero, Mendeley)", |_| {
+            Box::new(CslJsonWriter::default())
+        });
+        registry.register("endnote-xml", "EndNote XML records", |_| Box::new(EndnoteXmlWriter));
+        registry
+    }
+}
+
+/// The JSON array written by `search`, or a single object for `fetch`
+#[derive(Debug, Default)]
+pub struct JsonWriter {
+    count: usize,
+}
+
+impl Formatter for JsonWriter {
+    fn begin(&mut self) -> Result<String> {
+        Ok("[".to_string())
+    }
+
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        let item = json_array_item(self.count, &serde_json::to_string_pretty(paper)?);
+        self.count += 1;
+        Ok(item)
+    }
+
+    fn end(&mut self) -> Result<String> {
+        Ok(json_array_end(self.count))
+    }
+
+    fn single(&mut self, paper: &Paper) -> Result<String> {
+        Ok(format!("{}\n", serde_json::to_string_pretty(paper)?))
+    }
+}
+
+/// One compact JSON object per line
+#[derive(Debug, Default)]
+pub struct NdjsonWriter;
+
+impl Formatter for NdjsonWriter {
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        Ok(format!("{}\n", serde_json::to_string(paper)?))
+    }
+}
+
+/// Element of a pretty-printed JSON array, indented the way `serde_json` indents arrays
+fn json_array_item(index: usize, pretty: &str) -> String {
+    let separator = if index == 0 { "\n" } else { ",\n" };
+    let indented = pretty.lines().map(|line| format!("  {}", line)).collect::<Vec<_>>();
+    format!("{}{}", separator, indented.join("\n"))
+}
+
+fn json_array_end(count: usize) -> String {
+    if count == 0 { "]\n".to_string() } else { "\n]\n".to_string() }
+}
+
+/// Split a display name into given and family names, keeping particles such as `van der` with
+/// the family name
+fn split_name(name: &str) -> (String, String) {
+    let words = name.split_whitespace().collect::<Vec<_>>();
+    let Some((_, rest)) = words.split_last() else {
+        return (String::new(), String::new());
+    };
+    let particles = rest
+        .iter()
+        .rev()
+        .take_while(|word| word.chars().next().is_some_and(char::is_lowercase))
+        .count()
+        .min(rest.len().saturating_sub(1));
+    let split = rest.len() - particles;
+    (words[..split].join(" "), words[split..].join(" "))
+}
+
+/// Publication date: the first version's submission, falling back to the raw date text
+fn published_date(paper: &Paper) -> Option<NaiveDate> {
+    paper.submitted.or_else(|| crate::date::parse(&paper.published_date))
+}
+
+/// Collapse runs of whitespace, including line breaks, into single spaces
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Papers used by the golden-file tests of every format
+#[cfg(test)]
+fn sample_papers() -> Vec<Paper> {
+    vec![
+        Paper {
+            id: "1706.03762".parse().unwrap(),
+            title: "Attention Is All You Need".to_string(),
+            authors: vec![
+                "Ashish Vaswani".to_string(),
+                "Łukasz Kaiser".to_string(),
+                "Johannes van der Waals".to_string(),
+            ],
+            summary: "The dominant sequence transduction models are based on complex\n\
+                      recurrent & convolutional neural networks."
+                .to_string(),
+            published_date: "2017-06-12T17:57:34Z".to_string(),
+            url: "https://arxiv.org/abs/1706.03762".to_string(),
+            pdf_url: "https://arxiv.org/pdf/1706.03762".to_string(),
+            primary_category: Some("cs.CL".to_string()),
+            doi: Some("10.48550/arXiv.1706.03762".to_string()),
+            journal_ref: Some("Advances in Neural Information Processing Systems 30".to_string()),
+            ..Default::default()
+        },
+        Paper {
+            id: "2310.19736".parse().unwrap(),
+            title: "Evaluating <LLMs> on R&D \"tasks\"".to_string(),
+            authors: vec!["Grace Hopper".to_string()],
+            summary: "A short abstract.".to_string(),
+            published_date: "30 October, 2023".to_string(),
+            url: "https://arxiv.org/abs/2310.19736".to_string(),
+            pdf_url: "https://arxiv.org/pdf/2310.19736".to_string(),
+            ..Default::default()
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = Registry::default();
+        let names = registry.formats().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, ["json", "ndjson", "bibtex", "ris", "csl-json", "endnote-xml"]);
+
+        assert!(registry.get("RIS", &FormatOptions::default()).is_ok());
+        let err = registry.get("docx", &FormatOptions::default()).err().unwrap();
+        assert!(err.to_string().contains("expected one of json, ndjson"));
+    }
+
+    #[test]
+    fn test_registry_register_custom_format() {
+        struct Ids;
+        impl Formatter for Ids {
+            fn paper(&mut self, paper: &Paper) -> Result<String> {
+                Ok(format!("{}\n", paper.id))
+            }
+        }
+
+        let mut registry = Registry::default();
+        registry.register("ids", "arXiv IDs only", |_| Box::new(Ids));
+        let mut formatter = registry.get("ids", &FormatOptions::default()).unwrap();
+        assert_eq!(formatter.format(&sample_papers()).unwrap(), "1706.03762\n2310.19736\n");
+    }
+
+    #[test]
+    fn test_json_matches_serde_pretty_output() {
+        let papers = sample_papers();
+        let expected = format!("{}\n", serde_json::to_string_pretty(&papers).unwrap());
+        assert_eq!(JsonWriter::default().format(&papers).unwrap(), expected);
+        assert_eq!(JsonWriter::default().format(&[]).unwrap(), "[]\n");
+
+        let single = JsonWriter::default().single(&papers[0]).unwrap();
+        assert_eq!(single, format!("{}\n", serde_json::to_string_pretty(&papers[0]).unwrap()));
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let output = NdjsonWriter.format(&sample_papers()).unwrap();
+        let ids = output
+            .lines()
+            .map(|line| serde_json::from_str::<Paper>(line).unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1706.03762", "2310.19736"]);
+    }
+
+    /// Output of a registered format for the sample papers
+    fn sample_output(name: &str) -> String {
+        let mut formatter = Registry::default().get(name, &FormatOptions::default()).unwrap();
+        formatter.format(&sample_papers()).unwrap()
+    }
+
+    #[test]
+    fn test_golden_files() {
+        let golden = [
+            ("bibtex", include_str!("testdata/sample.bib")),
+            ("ris", include_str!("testdata/sample.ris")),
+            ("csl-json", include_str!("testdata/sample.csl.json")),
+            ("endnote-xml", include_str!("testdata/sample.endnote.xml")),
+        ];
+        for (name, expected) in golden {
+            assert_eq!(sample_output(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_citation_formats_keep_paper_fields() {
+        type Escape = fn(&str) -> String;
+        // Text opening each record, and how the format escapes text
+        let formats: [(&str, &str, Escape); 4] = [
+            ("bibtex", "@", bibtex::escape_latex),
+            ("ris", "TY  - ", str::to_string),
+            ("csl-json", "\"id\": ", |text| {
+                let quoted = serde_json::to_string(text).unwrap();
+                quoted[1..quoted.len() - 1].to_string()
+            }),
+            ("endnote-xml", "<record>", |text| quick_xml::escape::escape(text).into_owned()),
+        ];
+        let papers = sample_papers();
+        for (name, record_start, escape) in formats {
+            let output = sample_output(name);
+            let records = output.split(record_start).skip(1).collect::<Vec<_>>();
+            assert_eq!(records.len(), papers.len(), "{}", name);
+
+            for (paper, record) in papers.iter().zip(records) {
+                let year = published_date(paper).unwrap().format("%Y").to_string();
+                let mut expected = vec![escape(&paper.title), paper.id.to_string(), year];
+                expected.extend(paper.authors.iter().map(|author| escape(&split_name(author).1)));
+                expected.extend(paper.doi.clone());
+                expected.extend(paper.journal_ref.as_deref().map(escape));
+                for value in expected {
+                    assert!(
+                        record.contains(&value),
+                        "{} record lacks {:?}:\n{}",
+                        name,
+                        value,
+                        record
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_name() {
+        assert_eq!(split_name("Ashish Vaswani"), ("Ashish".into(), "Vaswani".into()));
+        assert_eq!(
+            split_name("Johannes van der Waals"),
+            ("Johannes".into(), "van der Waals".into())
+        );
+        assert_eq!(split_name("danah boyd"), ("danah".into(), "boyd".into()));
+        assert_eq!(split_name("Plato"), ("".into(), "Plato".into()));
+    }
+
+    #[test]
+    fn test_published_date() {
+        let papers = sample_papers();
+        assert_eq!(published_date(&papers[0]), NaiveDate::from_ymd_opt(2017, 6, 12));
+        assert_eq!(published_date(&papers[1]), NaiveDate::from_ymd_opt(2023, 10, 30));
+        let paper = Paper { published_date: "17 Jun 2017".to_string(), ..Default::default() };
+        assert_eq!(published_date(&paper), NaiveDate::from_ymd_opt(2017, 6, 17));
+        let paper = Paper {
+            published_date: "Submitted 30 October, 2023".to_string(),
+            submitted: NaiveDate::from_ymd_opt(2023, 10, 27),
+            ..Default::default()
+        };
+        assert_eq!(published_date(&paper), NaiveDate::from_ymd_opt(2023, 10, 27));
+    }
+}
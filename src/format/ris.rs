@@ -0,0 +1,74 @@
+//! RIS export, as imported by Zotero, Mendeley and EndNote
+
+use super::{Formatter, published_date, single_line, split_name};
+use crate::models::Paper;
+use anyhow::Result;
+
+/// Writes one RIS record per paper; preprints are `UNPB`, published papers `JOUR`
+#[derive(Debug, Default)]
+pub struct RisWriter;
+
+impl Formatter for RisWriter {
+    fn paper(&mut self, paper: &Paper) -> Result<String> {
+        let mut tags =
+            vec![("TY", if paper.journal_ref.is_some() { "JOUR" } else { "UNPB" }.into())];
+        tags.push(("TI", single_line(&paper.title)));
+        for author in &paper.authors {
+            tags.push((
+                "AU",
+                match split_name(author) {
+                    (given, family) if given.is_empty() => family,
+                    (given, family) => format!("{}, {}", family, given),
+                },
+            ));
+        }
+        if let Some(date) = published_date(paper) {
+            tags.push(("PY", date.format("%Y").to_string()));
+            tags.push(("DA", date.format("%Y/%m/%d").to_string()));
+        }
+        tags.push(("AB", single_line(&paper.summary)));
+        if let Some(journal) = &paper.journal_ref {
+            tags.push(("JO", single_line(journal)));
+        } else {
+            tags.push(("PB", "arXiv".to_string()));
+        }
+        if let Some(doi) = &paper.doi {
+            tags.push(("DO", doi.clone()));
+        }
+        tags.push(("AN", format!("arXiv:{}", paper.id)));
+        if let Some(primary) = &paper.primary_category {
+            tags.push(("KW", primary.clone()));
+        }
+        tags.push(("UR", paper.url.clone()));
+        tags.push(("L1", paper.pdf_url.clone()));
+
+        let mut record = String::new();
+        for (tag, value) in tags {
+            if !value.is_empty() {
+                record.push_str(&format!("{}  - {}\n", tag, value));
+            }
+        }
+        record.push_str("ER  - \n\n");
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::sample_papers;
+
+    #[test]
+    fn test_tag_order() {
+        let output = RisWriter.format(&sample_papers()).unwrap();
+        let records = output.split("\n\n").filter(|record| !record.is_empty());
+        let tags = records
+            .map(|record| record.lines().map(|line| &line[..2]).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>();
+        // TY opens each record and ER closes it; empty values such as a missing KW are left out
+        assert_eq!(
+            tags,
+            ["TY TI AU AU AU PY DA AB JO DO AN KW UR L1 ER", "TY TI AU PY DA AB PB AN UR L1 ER",]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Hosts whose URLs are accepted as paper references
+const ARXIV_HOSTS: &[&str] = &["arxiv.org", "www.arxiv.org", "export.arxiv.org"];
+
+/// Hosts resolving DOIs
+const DOI_HOSTS: &[&str] = &["doi.org", "dx.doi.org"];
+
+/// DOI prefix arXiv registers every paper under, e.g. `10.48550/arXiv.2301.00001`
+const ARXIV_DOI_PREFIX: &str = "10.48550/arxiv.";
+
+/// Normalised arXiv identifier, optionally pinned to a version
+///
+/// Parses new-style (`2301.00001`) and old-style (`hep-th/9901001`) identifiers as well as
+/// `arXiv:` prefixes, abstract/PDF/HTML URLs and arXiv DOIs, with or without a `vN` suffix.
+/// The default value is empty and only serves as a placeholder while a paper is being assembled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ArxivId {
+    base: String,
+    version: Option<u32>,
+}
+
+impl ArxivId {
+    /// Identifier without the version suffix
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    pub fn with_version(&self, version: u32) -> Self {
+        Self { base: self.base.clone(), version: Some(version) }
+    }
+
+    pub fn without_version(&self) -> Self {
+        Self { base: self.base.clone(), version: None }
+    }
+
+    /// Whether this is a pre-2007 `archive/YYMMNNN` identifier
+    pub fn is_old_style(&self) -> bool {
+        self.base.contains('/')
+    }
+
+    /// Abstract page, pinned to the version when one is set
+    pub fn abs_url(&self) -> String {
+        format!("https://arxiv.org/abs/{}", self)
+    }
+
+    /// PDF download, pinned to the version when one is set
+    pub fn pdf_url(&self) -> String {
+        format!("https://arxiv.org/pdf/{}", self)
+    }
+
+    /// Strip URL, DOI and `arXiv:` wrappers down to the bare `id[vN]`
+    fn unwrap_reference(input: &str) -> Result<&str> {
+        let mut rest = input.trim();
+        rest = rest.split(['?', '#']).next().unwrap_or_default();
+
+        if let Some((scheme, after)) = rest.split_once("://") {
+            if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+                return Err(anyhow!("Unsupported URL scheme: {}", scheme));
+            }
+            let (host, path) = after.split_once('/').unwrap_or((after, ""));
+            let host = host.to_ascii_lowercase();
+            rest = if ARXIV_HOSTS.contains(&host.as_str()) {
+                ["abs/", "pdf/", "html/"]
+                    .iter()
+                    .find_map(|prefix| path.strip_prefix(prefix))
+                    .ok_or_else(|| anyhow!("Not an arXiv paper URL: {}", input))?
+            } else if DOI_HOSTS.contains(&host.as_str()) {
+                path
+            } else {
+                return Err(anyhow!("Not an arXiv URL: {}", input));
+            };
+        }
+
+        if rest.len() > ARXIV_DOI_PREFIX.len()
+            && rest.is_char_boundary(ARXIV_DOI_PREFIX.len())
+            && rest[..ARXIV_DOI_PREFIX.len()].eq_ignore_ascii_case(ARXIV_DOI_PREFIX)
+        {
+            rest = &rest[ARXIV_DOI_PREFIX.len()..];
+        } else if rest.len() > 6
+            && rest.is_char_boundary(6)
+            && rest[..6].eq_ignore_ascii_case("arxiv:")
+        {
+            rest = &rest[6..];
+        }
+
+        Ok(rest.trim_end_matches('/').trim_end_matches(".pdf"))
+    }
+
+    /// Split a trailing `vN` off an identifier
+    fn split_version(id: &str) -> Result<(&str, Option<u32>)> {
+        match id.rfind(['v', 'V']) {
+            Some(pos)
+                if pos > 0
+                    && id[..pos].ends_with(|c: char| c.is_ascii_digit())
+                    && pos + 1 < id.len()
+                    && id[pos + 1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                let version = id[pos + 1..].parse()?;
+                if version == 0 {
+                    return Err(anyhow!("Invalid arXiv version: v0"));
+                }
+                Ok((&id[..pos], Some(version)))
+            }
+            _ => Ok((id, None)),
+        }
+    }
+
+    /// Validate and normalise the case of an identifier without version
+    fn normalize_base(base: &str) -> Option<String> {
+        if let Some((number, sequence)) = base.split_once('.')
+            && number.len() == 4
+            && (sequence.len() == 4 || sequence.len() == 5)
+            && number.chars().chain(sequence.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Some(base.to_string());
+        }
+
+        let (archive, number) = base.split_once('/')?;
+        let (archive, subject) = match archive.split_once('.') {
+            Some((archive, subject)) => (archive, Some(subject)),
+            None => (archive, None),
+        };
+        let valid = !archive.is_empty()
+            && archive.chars().all(|c| c.is_ascii_alphabetic() || c == '-')
+            && subject.is_none_or(|s| s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()))
+            && number.len() == 7
+            && number.chars().all(|c| c.is_ascii_digit());
+        if !valid {
+            return None;
+        }
+        Some(match subject {
+            Some(subject) => {
+                format!(
+                    "{}.{}/{}",
+                    archive.to_ascii_lowercase(),
+                    subject.to_ascii_uppercase(),
+                    number
+                )
+            }
+            None => format!("{}/{}", archive.to_ascii_lowercase(), number),
+        })
+    }
+}
+
+impl FromStr for ArxivId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let id = Self::unwrap_reference(s)?;
+        let (base, version) = Self::split_version(id)?;
+        let base = Self::normalize_base(base)
+            .ok_or_else(|| anyhow!("Invalid arXiv identifier: {:?}", s))?;
+        Ok(Self { base, version })
+    }
+}
+
+impl fmt::Display for ArxivId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{}v{}", self.base, version),
+            None => write!(f, "{}", self.base),
+        }
+    }
+}
+
+impl TryFrom<String> for ArxivId {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<ArxivId> for String {
+    fn from(id: ArxivId) -> Self {
+        id.to_string()
+    }
+}
+
+impl PartialEq<str> for ArxivId {
+    fn eq(&self, other: &str) -> bool {
+        match self.version {
+            None => self.base == other,
+            Some(version) => other
+                .strip_prefix(self.base.as_str())
+                .and_then(|rest| rest.strip_prefix('v'))
+                .is_some_and(|rest| !rest.starts_with('0') && rest.parse() == Ok(version)),
+        }
+    }
+}
+
+impl PartialEq<&str> for ArxivId {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> ArxivId {
+        s.parse().unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    #[test]
+    fn test_parse_new_style() {
+        let id = parse("2301.00001");
+        assert_eq!(id.base(), "2301.00001");
+        assert_eq!(id.version(), None);
+        assert!(!id.is_old_style());
+
+        let id = parse("0704.0001v3");
+        assert_eq!(id.base(), "0704.0001");
+        assert_eq!(id.version(), Some(3));
+        assert_eq!(id.to_string(), "0704.0001v3");
+    }
+
+    #[test]
+    fn test_parse_old_style() {
+        let id = parse("hep-th/9901001v2");
+        assert_eq!(id.base(), "hep-th/9901001");
+        assert_eq!(id.version(), Some(2));
+        assert!(id.is_old_style());
+
+        assert_eq!(parse("solv-int/9901001").base(), "solv-int/9901001");
+        assert_eq!(parse("solv-int/9901001v1").version(), Some(1));
+        assert_eq!(parse("math.gt/0309136").base(), "math.GT/0309136");
+    }
+
+    #[test]
+    fn test_parse_wrapped_forms() {
+        let expected = parse("2301.00001v2");
+        for input in [
+            "arXiv:2301.00001v2",
+            "ARXIV:2301.00001v2",
+            "https://arxiv.org/abs/2301.00001v2",
+            "http://export.arxiv.org/abs/2301.00001v2?context=cs",
+            "https://arxiv.org/pdf/2301.00001v2",
+            "https://arxiv.org/pdf/2301.00001v2.pdf",
+            "https://arxiv.org/html/2301.00001v2/",
+            "https://www.arxiv.org/abs/2301.00001v2#S1",
+            " 2301.00001v2 ",
+        ] {
+            assert_eq!(parse(input), expected, "{}", input);
+        }
+
+        assert_eq!(parse("10.48550/arXiv.2301.00001"), parse("2301.00001"));
+        assert_eq!(parse("https://doi.org/10.48550/arXiv.2301.00001"), parse("2301.00001"));
+        assert_eq!(parse("https://arxiv.org/abs/hep-th/9901001v2"), parse("hep-th/9901001v2"));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for input in [
+            "",
+            "foo",
+            "2301.001",
+            "2301.000001",
+            "2301.00001v0",
+            "2301.00001v",
+            "hep-th/990100",
+            "https://example.com/abs/2301.00001",
+            "https://arxiv.org/list/cs.CL/recent",
+            "10.1000/xyz",
+            "ftp://arxiv.org/abs/2301.00001",
+        ] {
+            assert!(input.parse::<ArxivId>().is_err(), "{:?} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn test_urls_and_versions() {
+        let id = parse("2301.00001");
+        assert_eq!(id.abs_url(), "https://arxiv.org/abs/2301.00001");
+        assert_eq!(id.with_version(4).pdf_url(), "https://arxiv.org/pdf/2301.00001v4");
+        assert_eq!(id.with_version(4).without_version(), id);
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let id = parse("hep-th/9901001v2");
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, "\"hep-th/9901001v2\"");
+        assert_eq!(serde_json::from_str::<ArxivId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<ArxivId>("\"not an id\"").is_err());
+    }
+}
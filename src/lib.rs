@@ -0,0 +1,46 @@
+//! Search and fetch papers from arXiv.
+//!
+//! The crate powers the `arxiv-cli` binary and can be embedded in other tools. Papers are
+//! retrieved either by scraping arxiv.org through Chrome (`browser` feature) or from the arXiv
+//! Atom API, and full texts are extracted from PDFs when the `pdf` feature is enabled.
+//!
+//! ```no_run
+//! use arxiv_cli::{ArxivClient, Backend, SearchOptions};
+//!
+//! # async fn run() -> arxiv_cli::Result<()> {
+//! let client = ArxivClient::builder().backend_kind(Backend::Api).build().await?;
+//! let papers = client.search(&SearchOptions::new("LLM").limit(10)).await?;
+//! for paper in papers {
+//!     println!("{}: {}", paper.id, paper.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod arxiv_search;
+pub mod backend;
+pub mod category;
+#[cfg(feature = "browser")]
+pub mod cdp;
+pub mod config;
+pub mod date;
+pub mod diff;
+pub mod format;
+pub mod id;
+pub mod models;
+pub mod query;
+
+pub use arxiv_search::{
+    ArxivClient, ArxivClientBuilder, DateType, SearchOptions, SortBy, SortOrder,
+};
+pub use category::Category;
+pub use config::{Backend, Config, HeadlessMode, Sandbox};
+pub use format::{CiteKey, Formatter};
+pub use id::ArxivId;
+pub use models::{Paper, Paragraph, Version};
+pub use query::{Field, Operator, SearchQuery};
+
+/// Error type returned by the client
+pub use anyhow::Error;
+/// Result type returned by the client
+pub use anyhow::Result;
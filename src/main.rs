@@ -1,11 +1,19 @@
-mod arxiv_search;
-mod cdp;
-mod config;
-mod models;
-
-use arxiv_search::ArxivClient;
-use clap::{Parser, Subcommand};
-use config::Config;
+use anyhow::anyhow;
+#[cfg(feature = "browser")]
+use arxiv_cli::cdp::discovery::{self, Candidate};
+use arxiv_cli::format::{FormatOptions, Formatter, Registry};
+use arxiv_cli::query::Term;
+use arxiv_cli::{
+    ArxivClient, ArxivId, Backend, Category, CiteKey, Config, DateType, Field, HeadlessMode,
+    Operator, Paper, Sandbox, SearchOptions, SearchQuery, SortBy, SortOrder,
+};
+use arxiv_cli::{date, diff};
+use chrono::NaiveDate;
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures::{Stream, StreamExt};
+use std::process::ExitCode;
+use tokio::sync::watch;
 
 #[derive(Parser)]
 #[command(name = "arxiv-cli")]
@@ -15,6 +23,39 @@ struct Cli {
     #[arg(long)]
     head: bool,
 
+    /// Headless implementation (overrides the `headless_mode` config key)
+    #[arg(long, value_enum)]
+    headless_mode: Option<HeadlessMode>,
+
+    /// Run Chrome without its sandbox, e.g. as root in a container (done automatically for root)
+    #[arg(long)]
+    no_sandbox: bool,
+
+    /// Keep Chrome's shared memory out of a small `/dev/shm`, as in Docker
+    #[arg(long)]
+    disable_dev_shm_usage: bool,
+
+    /// Browser window size as WIDTHxHEIGHT (overrides the `window_size` config key)
+    #[arg(long, value_parser = Config::parse_window_size)]
+    window_size: Option<String>,
+
+    /// User-agent of the browser (overrides the `user_agent` config key)
+    #[arg(long)]
+    user_agent: Option<String>,
+
+    /// Extra argument passed to Chrome, added to the `chrome_args` config key; repeatable
+    #[arg(long = "chrome-arg", value_name = "ARG", allow_hyphen_values = true)]
+    chrome_args: Vec<String>,
+
+    /// Use the running browser at this DevTools address (`http://host:9222` or a `ws://`
+    /// endpoint) instead of launching one (overrides the `browser_url` config key)
+    #[arg(long, value_parser = Config::parse_browser_url)]
+    browser_url: Option<String>,
+
+    /// Backend used to talk to arXiv (overrides the `backend` config key)
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,32 +63,102 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Search for papers
+    ///
+    /// A search stopped by Ctrl-C or SIGTERM still writes a complete document of the papers
+    /// received so far. Only the exit status (130 or 143) and a note on stderr mark it as
+    /// partial; the output itself looks like that of a finished search.
     Search {
-        /// Search query
-        #[arg(short, long)]
-        query: String,
+        #[command(flatten)]
+        query: Box<QueryArgs>,
+
+        /// Output format, written paper by paper as results arrive
+        #[arg(long, default_value = "json", value_parser = format_parser())]
+        format: String,
+
+        /// Citation key scheme for BibTeX output (overrides the `cite_key` config key)
+        #[arg(long, value_enum)]
+        cite_key: Option<CiteKey>,
 
         /// Limit the number of results
         #[arg(short, long)]
         limit: Option<usize>,
 
         /// Filter by date (after), YYYY-MM-DD
-        #[arg(long)]
-        after: Option<String>,
+        #[arg(long, value_parser = date::parse_ymd)]
+        after: Option<NaiveDate>,
 
         /// Filter by date (before), YYYY-MM-DD
+        #[arg(long, value_parser = date::parse_ymd)]
+        before: Option<NaiveDate>,
+
+        /// Restrict to a subject group, archive or category (e.g. cs, hep-th, cs.CL); repeatable
+        #[arg(long = "category", value_name = "CATEGORY")]
+        categories: Vec<Category>,
+
+        /// Exclude cross-listed papers when filtering by group or archive
         #[arg(long)]
-        before: Option<String>,
+        exclude_cross_list: bool,
+
+        /// Date the --after/--before filters apply to
+        #[arg(long, value_enum, default_value_t)]
+        date_type: DateType,
+
+        /// Sort key for the results; searches with more hits than arXiv pages through for one
+        /// query (10,000) need a date sort, which harvests them in date windows
+        #[arg(long, value_enum, default_value_t)]
+        sort: SortBy,
+
+        /// Sort direction
+        #[arg(long, value_enum, default_value_t)]
+        order: SortOrder,
     },
     /// Fetch paper details by ID
     Fetch {
-        /// Arxiv ID
-        id: String,
+        /// arXiv ID, `arXiv:` reference, abstract/PDF URL or arXiv DOI; a `vN` suffix pins the
+        /// version
+        id: ArxivId,
 
         /// Output raw HTML
         #[arg(long)]
         raw: bool,
+
+        /// Output format
+        #[arg(long, default_value = "json", value_parser = format_parser())]
+        format: String,
+
+        /// Citation key scheme for BibTeX output (overrides the `cite_key` config key)
+        #[arg(long, value_enum)]
+        cite_key: Option<CiteKey>,
+    },
+    /// List every version of a paper with its submission time and size
+    Versions {
+        /// arXiv ID, `arXiv:` reference, abstract/PDF URL or arXiv DOI
+        id: ArxivId,
     },
+    /// Compare the text of two versions of a paper
+    Diff {
+        /// arXiv ID, `arXiv:` reference, abstract/PDF URL or arXiv DOI
+        id: ArxivId,
+
+        /// Older version, e.g. `v1`
+        #[arg(long, value_parser = parse_version)]
+        from: u32,
+
+        /// Newer version, e.g. `v3`; defaults to the latest version
+        #[arg(long, value_parser = parse_version)]
+        to: Option<u32>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: DiffFormat,
+
+        /// Lines of context around each change in unified output
+        #[arg(long, default_value_t = 1)]
+        context: usize,
+    },
+    /// List the Chrome-family browsers found on this system and which one would be launched
+    #[cfg(feature = "browser")]
+    Browsers,
     /// Configuration management
     Config {
         #[command(subcommand)]
@@ -55,6 +166,117 @@ enum Commands {
     },
 }
 
+/// Accept the names in the output format registry, listing their descriptions in `--help`
+fn format_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        Registry::default().formats().map(|(name, help)| PossibleValue::new(name).help(help)),
+    )
+}
+
+/// Parse a version given as `v3` or `3`
+fn parse_version(s: &str) -> anyhow::Result<u32> {
+    let digits = s.strip_prefix(['v', 'V']).unwrap_or(s);
+    match digits.parse() {
+        Ok(version) if version > 0 => Ok(version),
+        _ => Err(anyhow!("expected a version such as v1, got {:?}", s)),
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum DiffFormat {
+    /// Unified diff with one line per paragraph
+    #[default]
+    Unified,
+    /// Inline word diff marking `[-deletions-]` and `{+insertions+}`
+    Words,
+    /// Added, removed and changed paragraphs plus metadata changes
+    Json,
+}
+
+/// Search terms; all terms are combined with AND unless given through `--or` or `--not`
+#[derive(Args)]
+struct QueryArgs {
+    /// Free-text query matched against all fields
+    #[arg(short, long)]
+    query: Option<String>,
+
+    /// Match in title
+    #[arg(long)]
+    title: Vec<String>,
+
+    /// Match in author names
+    #[arg(long)]
+    author: Vec<String>,
+
+    /// Match in abstract
+    #[arg(long = "abstract")]
+    abstract_: Vec<String>,
+
+    /// Match in comments
+    #[arg(long)]
+    comments: Vec<String>,
+
+    /// Match in journal reference
+    #[arg(long)]
+    journal_ref: Vec<String>,
+
+    /// Match in report number
+    #[arg(long)]
+    report_number: Vec<String>,
+
+    /// Match an arXiv ID
+    #[arg(long)]
+    arxiv_id: Vec<String>,
+
+    /// Match a DOI
+    #[arg(long)]
+    doi: Vec<String>,
+
+    /// Match an author ORCID
+    #[arg(long)]
+    orcid: Vec<String>,
+
+    /// Alternative term joined with OR, e.g. `--or ti:transformer`
+    #[arg(long, value_name = "[FIELD:]TERM")]
+    or: Vec<Term>,
+
+    /// Excluded term joined with NOT, e.g. `--not au:Smith`
+    #[arg(long, value_name = "[FIELD:]TERM")]
+    not: Vec<Term>,
+}
+
+impl QueryArgs {
+    fn into_query(self) -> SearchQuery {
+        let mut query = SearchQuery::new();
+        if let Some(q) = self.query {
+            query = query.and(Field::All, q);
+        }
+        let fielded = [
+            (Field::Title, self.title),
+            (Field::Author, self.author),
+            (Field::Abstract, self.abstract_),
+            (Field::Comments, self.comments),
+            (Field::JournalRef, self.journal_ref),
+            (Field::ReportNumber, self.report_number),
+            (Field::Id, self.arxiv_id),
+            (Field::Doi, self.doi),
+            (Field::Orcid, self.orcid),
+        ];
+        for (field, values) in fielded {
+            for value in values {
+                query = query.and(field, value);
+            }
+        }
+        for term in self.or {
+            query = query.push(Operator::Or, term.field, term.value);
+        }
+        for term in self.not {
+            query = query.push(Operator::Not, term.field, term.value);
+        }
+        query
+    }
+}
+
 #[derive(Subcommand)]
 enum ConfigCommands {
     /// Set a config value
@@ -67,10 +289,123 @@ enum ConfigCommands {
     Path,
 }
 
+/// Termination signal the CLI shuts down on
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Interrupt,
+    Terminate,
+}
+
+impl Signal {
+    /// Wait for Ctrl-C or, on Unix, SIGTERM
+    async fn wait() -> Signal {
+        let ctrl_c = async {
+            // Without a handler Ctrl-C keeps its default behaviour of killing the process
+            if tokio::signal::ctrl_c().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        };
+        #[cfg(unix)]
+        if let Ok(mut terminate) =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        {
+            return tokio::select! {
+                _ = ctrl_c => Signal::Interrupt,
+                _ = terminate.recv() => Signal::Terminate,
+            };
+        }
+        ctrl_c.await;
+        Signal::Interrupt
+    }
+
+    /// Wait until a signal has been received on `signals`
+    async fn received(mut signals: watch::Receiver<Option<Signal>>) -> Signal {
+        if let Ok(signal) = signals.wait_for(Option::is_some).await
+            && let Some(signal) = *signal
+        {
+            return signal;
+        }
+        std::future::pending().await
+    }
+
+    /// Exit status for the signal, 128 plus its number as shells report it
+    fn exit_code(self) -> u8 {
+        match self {
+            Signal::Interrupt => 130,
+            Signal::Terminate => 143,
+        }
+    }
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Signal::Interrupt => write!(f, "SIGINT"),
+            Signal::Terminate => write!(f, "SIGTERM"),
+        }
+    }
+}
+
+/// The command was cut short by a signal
+#[derive(Debug)]
+struct Interrupted {
+    signal: Signal,
+    /// Papers written before stopping, for commands that stream their results
+    written: Option<usize>,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interrupted by {}", self.signal)?;
+        match self.written {
+            Some(written) => write!(f, "; output is partial ({} papers written)", written),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    let (signal_tx, signals) = watch::channel(None);
+    tokio::spawn(async move {
+        let signal = Signal::wait().await;
+        let _ = signal_tx.send(Some(signal));
+        // A second signal forces the exit when closing the output or the browser hangs; the
+        // status stays that of the first signal
+        Signal::wait().await;
+        eprintln!("Interrupted again; exiting without cleaning up");
+        std::process::exit(signal.exit_code().into());
+    });
+
+    // `run` is polled first so a search that sees the signal can still close its output; any
+    // other command is dropped, which cancels its requests and shuts the browser down
+    let result = tokio::select! {
+        biased;
+        result = run(cli, signals.clone()) => result,
+        signal = Signal::received(signals) => {
+            Err(Interrupted { signal, written: None }.into())
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => match e.downcast_ref::<Interrupted>() {
+            Some(interrupted) => {
+                eprintln!("{}", interrupted);
+                ExitCode::from(interrupted.signal.exit_code())
+            }
+            None => {
+                eprintln!("Error: {:?}", e);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+async fn run(cli: Cli, signals: watch::Receiver<Option<Signal>>) -> anyhow::Result<()> {
     let mut config = Config::load()?;
 
     if let Commands::Config { command } = &cli.command {
@@ -99,28 +434,195 @@ async fn main() -> anyhow::Result<()> {
     if cli.head {
         config.headless = false;
     }
+    if let Some(mode) = cli.headless_mode {
+        config.headless_mode = mode;
+    }
+    if cli.no_sandbox {
+        config.sandbox = Sandbox::Disabled;
+    }
+    config.disable_dev_shm_usage |= cli.disable_dev_shm_usage;
+    if let Some(size) = cli.window_size {
+        config.window_size = Some(size);
+    }
+    if let Some(user_agent) = cli.user_agent {
+        config.user_agent = Some(user_agent);
+    }
+    config.chrome_args.extend(cli.chrome_args);
+
+    #[cfg(feature = "browser")]
+    if let Commands::Browsers = cli.command {
+        let selected = match &config.browser_path {
+            Some(path) => Ok(Candidate { path: path.into(), source: "browser_path".to_string() }),
+            None => discovery::find(),
+        };
+        let report = serde_json::json!({
+            "selected": selected.as_ref().ok(),
+            "error": selected.as_ref().err().map(|e| e.to_string()),
+            "browser_url": cli.browser_url.as_ref().or(config.browser_url.as_ref()),
+            "candidates": discovery::candidates(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if let Some(url) = cli.browser_url {
+        config.browser_url = Some(url);
+    }
+
+    if let Some(backend) = cli.backend {
+        config.backend = backend;
+    }
 
     let client = ArxivClient::new(&config).await?;
 
     match cli.command {
-        Commands::Search { query, limit, after, before } => {
-            let papers = client.search(&query, limit, after, before).await?;
-            let json = serde_json::to_string_pretty(&papers)?;
-            println!("{}", json);
+        Commands::Search {
+            query,
+            format,
+            cite_key,
+            limit,
+            after,
+            before,
+            categories,
+            exclude_cross_list,
+            date_type,
+            sort,
+            order,
+        } => {
+            let options = SearchOptions {
+                query: query.into_query(),
+                limit,
+                after: after.map(|date| date.to_string()),
+                before: before.map(|date| date.to_string()),
+                date_type,
+                categories,
+                exclude_cross_list,
+                sort_by: sort,
+                sort_order: order,
+            };
+            let format_options = FormatOptions { cite_key: cite_key.unwrap_or(config.cite_key) };
+            let mut formatter = Registry::default().get(&format, &format_options)?;
+            let papers = client.search_stream(&options);
+            write_papers(formatter.as_mut(), papers, signals, emit).await?;
         }
-        Commands::Fetch { id, raw } => {
+        Commands::Fetch { id, raw, format, cite_key } => {
             if raw {
                 let bytes = client.fetch_pdf(&id).await?;
                 use std::io::Write;
                 std::io::stdout().write_all(&bytes)?;
             } else {
+                let options = FormatOptions { cite_key: cite_key.unwrap_or(config.cite_key) };
+                let mut formatter = Registry::default().get(&format, &options)?;
                 let paper = client.fetch(&id).await?;
-                let json = serde_json::to_string_pretty(&paper)?;
-                println!("{}", json);
+                emit(&formatter.single(&paper)?)?;
+            }
+        }
+        Commands::Versions { id } => {
+            let versions = client.versions(&id).await?;
+            let json = serde_json::to_string_pretty(&versions)?;
+            println!("{}", json);
+        }
+        Commands::Diff { id, from, to, format, context } => {
+            let new_id = to.map_or_else(|| id.without_version(), |to| id.with_version(to));
+            let old_id = id.with_version(from);
+            let (old, new) = futures::try_join!(client.fetch(&old_id), client.fetch(&new_id))?;
+            for (paper, id) in [(&old, &old_id), (&new, &new_id)] {
+                if paper.description_paragraphs.as_ref().is_none_or(Vec::is_empty) {
+                    #[cfg(not(feature = "pdf"))]
+                    return Err(anyhow!(
+                        "No text extracted for {}; diff needs the `pdf` feature",
+                        id
+                    ));
+                    #[cfg(feature = "pdf")]
+                    return Err(anyhow!("No text could be extracted from the PDF of {}", id));
+                }
+            }
+            match format {
+                DiffFormat::Unified => emit(&diff::unified(&old, &new, context))?,
+                DiffFormat::Words => emit(&diff::words(&diff::compare(&old, &new)))?,
+                DiffFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&diff::compare(&old, &new))?)
+                }
             }
         }
+        #[cfg(feature = "browser")]
+        Commands::Browsers => unreachable!(),
         Commands::Config { .. } => unreachable!(),
     }
 
     Ok(())
 }
+
+/// Write the papers of `papers` as one document through `out`
+///
+/// The document is closed even when the stream fails or a signal arrives, so the papers
+/// written so far stay parseable; the error that stopped the stream is returned afterwards.
+async fn write_papers(
+    formatter: &mut dyn Formatter,
+    papers: impl Stream<Item = anyhow::Result<Paper>>,
+    signals: watch::Receiver<Option<Signal>>,
+    mut out: impl FnMut(&str) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    out(&formatter.begin()?)?;
+    let mut papers = std::pin::pin!(papers);
+    let mut written = 0;
+    let stopped = loop {
+        tokio::select! {
+            biased;
+            signal = Signal::received(signals.clone()) => {
+                break Err(Interrupted { signal, written: Some(written) }.into());
+            }
+            paper = papers.next() => match paper {
+                Some(paper) => {
+                    if let Err(e) = paper.and_then(|paper| out(&formatter.paper(&paper)?)) {
+                        break Err(e);
+                    }
+                    written += 1;
+                }
+                None => break Ok(()),
+            },
+        }
+    };
+    let end = formatter.end().and_then(|chunk| out(&chunk));
+    stopped.and(end)
+}
+
+/// Write a chunk of formatted output and flush so consumers see each paper immediately
+fn emit(chunk: &str) -> anyhow::Result<()> {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(chunk.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(id: &str) -> Paper {
+        Paper { id: id.parse().unwrap(), title: format!("Paper {}", id), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_write_papers_closes_document_when_stream_fails() {
+        let papers = futures::stream::iter([
+            Ok(paper("2101.00001")),
+            Err(anyhow!("connection reset")),
+            Ok(paper("2101.00002")),
+        ]);
+        let mut formatter = Registry::default().get("json", &FormatOptions::default()).unwrap();
+        let (_signal_tx, signals) = watch::channel(None);
+        let mut output = String::new();
+        let result = write_papers(formatter.as_mut(), papers, signals, |chunk| {
+            output.push_str(chunk);
+            Ok(())
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "connection reset");
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["id"], "2101.00001");
+    }
+}
@@ -1,17 +1,74 @@
+use crate::id::ArxivId;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Paper {
-    pub id: String,
+    /// Identifier without version
+    pub id: ArxivId,
+    /// Version the metadata and PDF belong to; the latest unless a version was requested
+    #[serde(default)]
+    pub version: Option<u32>,
     pub title: String,
     pub authors: Vec<String>,
     pub summary: String,
+    /// Date text as shown by the source, kept for reference; see `submitted` and `updated`
     pub published_date: String,
+    /// Submission date of the first version
+    #[serde(default)]
+    pub submitted: Option<NaiveDate>,
+    /// Submission date of the latest version
+    #[serde(default)]
+    pub updated: Option<NaiveDate>,
+    /// Month the paper was first announced, as ISO-8601 `YYYY-MM`
+    #[serde(default)]
+    pub announced: Option<String>,
     pub url: String,
     pub pdf_url: String,
+    /// Primary subject class, e.g. `cs.CL`
+    #[serde(default)]
+    pub primary_category: Option<String>,
+    /// Primary and cross-list subject classes, primary first
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Author comments, typically page and figure counts
+    #[serde(default)]
+    pub comments: Option<String>,
+    /// DOI of the published version
+    #[serde(default)]
+    pub doi: Option<String>,
+    /// Journal reference of the published version
+    #[serde(default)]
+    pub journal_ref: Option<String>,
+    /// Institutional report number
+    #[serde(default)]
+    pub report_number: Option<String>,
+    /// Mathematics Subject Classification codes, e.g. `68T05`
+    #[serde(default)]
+    pub msc_classes: Vec<String>,
+    /// ACM Computing Classification codes, e.g. `I.2.7`
+    #[serde(default)]
+    pub acm_classes: Vec<String>,
+    /// URL of the license the paper is distributed under
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Submission history, oldest version first
+    #[serde(default)]
+    pub versions: Vec<Version>,
     pub description_paragraphs: Option<Vec<Paragraph>>,
 }
 
+/// One entry of a paper's submission history
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Version {
+    /// Version number, 1 for the original submission
+    pub version: u32,
+    /// Submission timestamp as shown by arXiv, e.g. `Mon, 12 Jun 2017 17:57:34 UTC`
+    pub submitted: String,
+    /// Size of the submission in kilobytes
+    pub size_kb: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Paragraph {
     pub number: String,
@@ -26,7 +83,7 @@ mod tests {
     #[test]
     fn test_paper_serialization() {
         let paper = Paper {
-            id: "1".to_string(),
+            id: "2401.00001".parse().unwrap(),
             title: "Test".to_string(),
             authors: vec!["Author".to_string()],
             summary: "Summary".to_string(),
@@ -38,9 +95,29 @@ mod tests {
                 id: "".to_string(),
                 text: "Text".to_string(),
             }]),
+            ..Default::default()
         };
         let json = serde_json::to_string(&paper).unwrap();
         assert!(json.contains("Test"));
         assert!(json.contains("description_paragraphs"));
     }
+
+    #[test]
+    fn test_paper_deserialize_without_optional_metadata() {
+        let json = r#"{"id":"2401.00001","title":"T","authors":[],"summary":"","published_date":"",
+            "url":"","pdf_url":"","description_paragraphs":null}"#;
+        let paper: Paper = serde_json::from_str(json).unwrap();
+        assert!(paper.categories.is_empty());
+        assert!(paper.comments.is_none());
+        assert!(paper.versions.is_empty());
+    }
+
+    #[test]
+    fn test_version_deserialize() {
+        let json = r#"[{"version":1,"submitted":"Mon, 12 Jun 2017 17:57:34 UTC","size_kb":1102},
+            {"version":2,"submitted":"Mon, 19 Jun 2017 16:49:45 UTC","size_kb":null}]"#;
+        let versions: Vec<Version> = serde_json::from_str(json).unwrap();
+        assert_eq!(versions[0].size_kb, Some(1102));
+        assert_eq!(versions[1].version, 2);
+    }
 }
@@ -0,0 +1,300 @@
+use anyhow::{Result, anyhow};
+use std::str::FromStr;
+
+/// Field a search term is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    All,
+    Title,
+    Author,
+    Abstract,
+    Comments,
+    JournalRef,
+    Category,
+    ReportNumber,
+    Id,
+    Doi,
+    Orcid,
+}
+
+impl Field {
+    /// Value of the `terms-N-field` parameter of the advanced search form
+    pub fn advanced_name(&self) -> &'static str {
+        match self {
+            Field::All => "all",
+            Field::Title => "title",
+            Field::Author => "author",
+            Field::Abstract => "abstract",
+            Field::Comments => "comments",
+            Field::JournalRef => "journal_ref",
+            Field::Category => "cross_list_category",
+            Field::ReportNumber => "report_num",
+            Field::Id => "paper_id",
+            Field::Doi => "doi",
+            Field::Orcid => "orcid",
+        }
+    }
+
+    /// Prefix used in `search_query` of the Atom API
+    ///
+    /// The API has no DOI or ORCID prefix, so those fall back to `all`.
+    pub fn api_prefix(&self) -> &'static str {
+        match self {
+            Field::All | Field::Doi | Field::Orcid => "all",
+            Field::Title => "ti",
+            Field::Author => "au",
+            Field::Abstract => "abs",
+            Field::Comments => "co",
+            Field::JournalRef => "jr",
+            Field::Category => "cat",
+            Field::ReportNumber => "rn",
+            Field::Id => "id",
+        }
+    }
+
+    /// Parse a `field:value` shorthand such as `au:Hinton` or `title:attention`
+    fn split_prefixed(input: &str) -> Option<(Field, &str)> {
+        let (prefix, value) = input.split_once(':')?;
+        let field = match prefix.to_ascii_lowercase().as_str() {
+            "all" => Field::All,
+            "ti" | "title" => Field::Title,
+            "au" | "author" => Field::Author,
+            "abs" | "abstract" => Field::Abstract,
+            "co" | "comments" => Field::Comments,
+            "jr" | "journal_ref" => Field::JournalRef,
+            "cat" | "category" => Field::Category,
+            "rn" | "report_num" => Field::ReportNumber,
+            "id" | "paper_id" => Field::Id,
+            "doi" => Field::Doi,
+            "orcid" => Field::Orcid,
+            _ => return None,
+        };
+        Some((field, value))
+    }
+}
+
+/// Boolean operator joining a term to the terms before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    And,
+    Or,
+    Not,
+}
+
+impl Operator {
+    fn advanced_name(&self) -> &'static str {
+        match self {
+            Operator::And => "AND",
+            Operator::Or => "OR",
+            Operator::Not => "NOT",
+        }
+    }
+
+    fn api_name(&self) -> &'static str {
+        match self {
+            Operator::And => "AND",
+            Operator::Or => "OR",
+            Operator::Not => "ANDNOT",
+        }
+    }
+}
+
+/// A single fielded search term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub operator: Operator,
+    pub field: Field,
+    pub value: String,
+}
+
+impl FromStr for Term {
+    type Err = anyhow::Error;
+
+    /// Parse `value` or `field:value` into an `AND` term
+    fn from_str(s: &str) -> Result<Self> {
+        let (field, value) = Field::split_prefixed(s).unwrap_or((Field::All, s));
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(anyhow!("Empty search term: {:?}", s));
+        }
+        Ok(Term { operator: Operator::And, field, value: value.to_string() })
+    }
+}
+
+/// Structured search query made of fielded terms joined by boolean operators
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    terms: Vec<Term>,
+}
+
+impl SearchQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn and(self, field: Field, value: impl Into<String>) -> Self {
+        self.push(Operator::And, field, value)
+    }
+
+    pub fn or(self, field: Field, value: impl Into<String>) -> Self {
+        self.push(Operator::Or, field, value)
+    }
+
+    pub fn not(self, field: Field, value: impl Into<String>) -> Self {
+        self.push(Operator::Not, field, value)
+    }
+
+    pub fn push(mut self, operator: Operator, field: Field, value: impl Into<String>) -> Self {
+        self.terms.push(Term { operator, field, value: value.into() });
+        self
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The free-text query when this is a single `all` term, as used by the simple search
+    pub fn as_simple(&self) -> Option<&str> {
+        match self.terms.as_slice() {
+            [Term { field: Field::All, value, .. }] => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Check that the query can be sent to arXiv
+    ///
+    /// The first term's operator is ignored by arXiv, so it must not be `NOT`.
+    pub fn validate(&self) -> Result<()> {
+        match self.terms.first() {
+            None => Err(anyhow!("Search query is empty")),
+            Some(term) if term.operator == Operator::Not => {
+                Err(anyhow!("Search query needs at least one term before a NOT term"))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Compile into the `terms-N-*` parameters of the advanced search form
+    pub fn to_advanced_params(&self) -> String {
+        self.terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| {
+                format!(
+                    "terms-{i}-operator={}&terms-{i}-term={}&terms-{i}-field={}",
+                    term.operator.advanced_name(),
+                    urlencoding::encode(&term.value),
+                    term.field.advanced_name()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Compile into the `search_query` syntax of the Atom API
+    pub fn to_api_query(&self) -> String {
+        let mut query = String::new();
+        for (i, term) in self.terms.iter().enumerate() {
+            let words = term
+                .value
+                .split_whitespace()
+                .map(|word| format!("{}:{}", term.field.api_prefix(), word))
+                .collect::<Vec<_>>();
+            let clause = if words.len() > 1 && self.terms.len() > 1 {
+                format!("({})", words.join(" AND "))
+            } else {
+                words.join(" AND ")
+            };
+
+            if i > 0 {
+                query.push_str(&format!(" {} ", term.operator.api_name()));
+            }
+            query.push_str(&clause);
+        }
+        query
+    }
+}
+
+impl From<&str> for SearchQuery {
+    /// Free-text query matched against all fields
+    fn from(value: &str) -> Self {
+        SearchQuery::new().and(Field::All, value)
+    }
+}
+
+impl From<String> for SearchQuery {
+    fn from(value: String) -> Self {
+        SearchQuery::new().and(Field::All, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_query() {
+        let query = SearchQuery::from("LLM");
+        assert_eq!(query.as_simple(), Some("LLM"));
+        assert!(SearchQuery::new().and(Field::Title, "LLM").as_simple().is_none());
+    }
+
+    #[test]
+    fn test_to_advanced_params() {
+        let query = SearchQuery::new()
+            .and(Field::Title, "attention")
+            .or(Field::Author, "Vaswani")
+            .not(Field::Abstract, "recurrent network");
+        assert_eq!(
+            query.to_advanced_params(),
+            "terms-0-operator=AND&terms-0-term=attention&terms-0-field=title&\
+             terms-1-operator=OR&terms-1-term=Vaswani&terms-1-field=author&\
+             terms-2-operator=NOT&terms-2-term=recurrent%20network&terms-2-field=abstract"
+        );
+    }
+
+    #[test]
+    fn test_to_api_query() {
+        let query = SearchQuery::new()
+            .and(Field::Title, "attention")
+            .and(Field::Category, "cs.CL")
+            .not(Field::All, "recurrent network");
+        assert_eq!(
+            query.to_api_query(),
+            "ti:attention AND cat:cs.CL ANDNOT (all:recurrent AND all:network)"
+        );
+        assert_eq!(
+            SearchQuery::from("machine learning").to_api_query(),
+            "all:machine AND all:learning"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SearchQuery::new().validate().is_err());
+        assert!(SearchQuery::new().not(Field::All, "x").validate().is_err());
+        assert!(SearchQuery::from("x").not(Field::All, "y").validate().is_ok());
+    }
+
+    #[test]
+    fn test_term_from_str() {
+        let term: Term = "au:Hinton".parse().unwrap();
+        assert_eq!(term.field, Field::Author);
+        assert_eq!(term.value, "Hinton");
+
+        let term: Term = "journal_ref:Nature".parse().unwrap();
+        assert_eq!(term.field, Field::JournalRef);
+
+        let term: Term = "doi:10.1000/xyz".parse().unwrap();
+        assert_eq!(term.field, Field::Doi);
+
+        let term: Term = "graph neural networks".parse().unwrap();
+        assert_eq!(term.field, Field::All);
+
+        assert!("ti:".parse::<Term>().is_err());
+    }
+}
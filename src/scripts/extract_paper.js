@@ -10,12 +10,48 @@ JSON.stringify((function () {
         const summary = abstractElement ? abstractElement.textContent.replace('Abstract:', '').trim() : '';
 
         const dateElement = document.querySelector('.dateline');
-        // Example: (Submitted on 17 Jun 2017)
-        const publishedDate = dateElement ? dateElement.textContent.replace('(Submitted on', '').replace(')', '').trim() : '';
+        // Example: [Submitted on 12 Jun 2017 (v1), last revised 2 Aug 2023 (this version, v7)]
+        // Kept as shown; the dates are parsed on the Rust side
+        const publishedDate = dateElement ? dateElement.textContent.replace(/\s+/g, ' ').trim() : '';
+
+        // Example: Computation and Language (cs.CL)
+        const primaryElement = document.querySelector('.primary-subject');
+        const primaryMatch = primaryElement ? primaryElement.textContent.match(/\(([^()]+)\)\s*$/) : null;
+        const primaryCategory = primaryMatch ? primaryMatch[1] : null;
+
+        const cellText = (name) => {
+            const cell = document.querySelector('td.tablecell.' + name);
+            return cell ? cell.textContent.trim() : null;
+        };
+        const splitClasses = (text) => text ? text.split(/[,;]/).map(c => c.trim()).filter(c => c) : [];
+
+        // Example: Computation and Language (cs.CL); Machine Learning (cs.LG)
+        const subjects = cellText('subjects') || '';
+        const categories = Array.from(subjects.matchAll(/\(([^()]+)\)/g), m => m[1]);
+
+        const doiElement = document.querySelector('td.tablecell.doi a');
+        const doi = doiElement ? doiElement.textContent.trim() : null;
+
+        const licenseElement = document.querySelector('.abs-license a');
+        const license = licenseElement ? licenseElement.href : null;
+
+        // Example: [v1] Mon, 12 Jun 2017 17:57:34 UTC (1,102 KB)
+        const versions = [];
+        const historyElement = document.querySelector('.submission-history');
+        const historyText = historyElement ? historyElement.textContent : '';
+        for (const m of historyText.matchAll(/\[v(\d+)\]\s*([^(]+?)\s*\(([\d,.]+)\s*(KB|MB)\)/g)) {
+            const size = parseFloat(m[3].replace(/,/g, ''));
+            versions.push({
+                version: parseInt(m[1], 10),
+                submitted: m[2].trim(),
+                size_kb: Math.round(m[4] === 'MB' ? size * 1024 : size)
+            });
+        }
 
         // URL from window location or link
         const url = window.location.href;
-        const id = url.split('/abs/')[1] ? url.split('/abs/')[1].split('v')[0] : ''; // Handle versions if necessary
+        // Old-style IDs contain a slash (hep-th/9901001v2); the version is stripped on the Rust side
+        const id = window.location.pathname.replace(/^\/abs\//, '');
         const pdfUrl = url.replace('/abs/', '/pdf/');
 
         return {
@@ -26,6 +62,16 @@ JSON.stringify((function () {
             published_date: publishedDate,
             url,
             pdf_url: pdfUrl,
+            primary_category: primaryCategory,
+            categories,
+            comments: cellText('comments'),
+            doi,
+            journal_ref: cellText('jref'),
+            report_number: cellText('report-number'),
+            msc_classes: splitClasses(cellText('msc-classes')),
+            acm_classes: splitClasses(cellText('acm-classes')),
+            license,
+            versions,
             description_paragraphs: null
         };
     } catch (e) {
@@ -10,26 +10,45 @@ JSON.stringify((function () {
             const authors = [];
             item.querySelectorAll('.authors > a').forEach(a => authors.push(a.textContent.trim()));
 
-            const abstractElement = item.querySelector('.abstract-short');
-            // Remove "Abstract" label and "Show Less" button text if present.
-            // abstract-short typically looks like: "... text ... ▽ More"
-            let summary = abstractElement ? abstractElement.textContent.trim() : '';
-
-            // Remove "Abstract:" prefix if it accidentally got included (usually failsafe)
-            if (summary.startsWith('Abstract:')) {
-                summary = summary.replace('Abstract:', '').trim();
+            // The full abstract is in the page but hidden behind "▽ More"; fall back to the short one
+            const abstractElement = item.querySelector('.abstract-full') || item.querySelector('.abstract-short');
+            let summary = '';
+            if (abstractElement) {
+                // Drop the "△ Less" / "▽ More" toggle links, keeping links that are part of the abstract
+                const abstractCopy = abstractElement.cloneNode(true);
+                abstractCopy.querySelectorAll('a').forEach(a => {
+                    if (/^\s*[△▲▽▼]\s*(Less|More)\s*$/.test(a.textContent)) {
+                        a.remove();
+                    }
+                });
+                // Kept as shown; whitespace and leftover toggle text are cleaned on the Rust side
+                summary = abstractCopy.textContent;
             }
 
-            // Remove trailing "More" link text
-            // The arrow can be ▽ or &#9661; (down triangle)
-            // We use a regex to be safe.
-            summary = summary.replace(/\s*[▽▼]\s*More$/, '').trim();
-
             const dateElement = item.querySelector('p.is-size-7');
-            const dateText = dateElement ? dateElement.textContent : '';
             // Example: "Submitted 30 October, 2023; originally announced October 2023."
-            // We want "30 October, 2023"
-            const publishedDate = dateText.split(';')[0].replace('Submitted', '').trim();
+            // Kept as shown; the dates are parsed on the Rust side
+            const publishedDate = dateElement ? dateElement.textContent.replace(/\s+/g, ' ').trim() : '';
+
+            // The primary category is the highlighted tag, cross-lists are grey
+            const primaryElement = item.querySelector('.tags .tag.is-link');
+            const primaryCategory = primaryElement ? primaryElement.textContent.trim() : null;
+            const categories = [];
+            item.querySelectorAll('.tags .tag[data-tooltip]').forEach(tag => categories.push(tag.textContent.trim()));
+
+            const doiElement = item.querySelector('.tags.has-addons a[href*="doi.org"]');
+            const doi = doiElement ? doiElement.textContent.trim() : null;
+
+            // Example: <p class="comments"><span>Journal ref:</span> Nature 1 (2020)</p>
+            const labelled = {};
+            item.querySelectorAll('p.comments').forEach(p => {
+                const label = p.querySelector('span');
+                if (label) {
+                    const name = label.textContent.trim();
+                    labelled[name] = p.textContent.replace(name, '').trim();
+                }
+            });
+            const splitClasses = (text) => text ? text.split(/[,;]/).map(c => c.trim()).filter(c => c) : [];
 
             const linkElement = item.querySelector('.list-title > a');
             const url = linkElement ? linkElement.href : '';
@@ -46,6 +65,16 @@ JSON.stringify((function () {
                     published_date: publishedDate,
                     url,
                     pdf_url: pdfUrl,
+                    primary_category: primaryCategory,
+                    categories,
+                    comments: labelled['Comments:'] || null,
+                    doi,
+                    journal_ref: labelled['Journal ref:'] || null,
+                    report_number: labelled['Report number:'] || null,
+                    msc_classes: splitClasses(labelled['MSC Class:']),
+                    acm_classes: splitClasses(labelled['ACM Class:']),
+                    license: null,
+                    versions: [],
                     description_paragraphs: null
                 });
             }
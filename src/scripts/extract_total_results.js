@@ -0,0 +1,7 @@
+(function () {
+    const titleElement = document.querySelector('h1.title');
+    if (!titleElement) return null;
+    // Example: "Showing 1–50 of 1,234 results for all: LLM"
+    const match = titleElement.textContent.match(/of\s+([\d,]+)\s+results/);
+    return match ? parseInt(match[1].replace(/,/g, ''), 10) : null;
+})()